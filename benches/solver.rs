@@ -0,0 +1,61 @@
+//cargo bench --bench solver, prints time of one evaluation of the accelerations
+use std::time::{Duration, Instant};
+
+use fyr_pendulum_too::{
+    dulum::Dulum, gravity::GravityField, pivot::Kinematics, solver::SolverKind,
+};
+use macroquad::prelude::WHITE;
+
+fn chain(count: usize) -> Vec<Dulum> {
+    (0..count)
+        .map(|i| {
+            let mut dulum = Dulum::new(
+                0.3 * (i as f64).sin(),
+                1.0 / count as f64,
+                1.0,
+                i % 5 == 4,
+                100.0,
+                1.0 / count as f64,
+                WHITE,
+                0.2,
+            );
+            dulum.angle_der = 0.5;
+            dulum
+        })
+        .collect()
+}
+
+//runs the solver until the budget is spent, returns time per evaluation
+fn measure(solver: SolverKind, dulums: &[Dulum], budget: Duration) -> Duration {
+    let (gravity, pivot) = (GravityField::default(), Kinematics::default());
+    let start = Instant::now();
+    let mut runs = 0;
+
+    while runs == 0 || start.elapsed() < budget {
+        std::hint::black_box(
+            solver
+                .accelerations(std::hint::black_box(dulums), &[], &gravity, &pivot)
+                .unwrap(),
+        );
+        runs += 1;
+    }
+
+    start.elapsed() / runs
+}
+
+fn main() {
+    let budget = Duration::from_millis(500);
+
+    println!("{:>8} {:>14} {:>14}", "dulums", "dense", "recursive");
+    for count in [2, 6, 25, 100, 400, 1000] {
+        let dulums = chain(count);
+        //the dense one takes seconds per evaluation for the longest chains
+        let dense = if count <= 400 {
+            format!("{:?}", measure(SolverKind::Dense, &dulums, budget))
+        } else {
+            "-".to_string()
+        };
+        let recursive = measure(SolverKind::Recursive, &dulums, budget);
+        println!("{count:>8} {dense:>14} {:>14}", format!("{recursive:?}"));
+    }
+}
@@ -0,0 +1,241 @@
+#![windows_subsystem = "windows"]
+
+use fyr_pendulum_too::{
+    fractal::{FlipJob, FlipSettings, Region},
+    integrator::IntegratorKind,
+    meth,
+};
+use macroquad::prelude::*;
+
+//square of the screen the map fills
+fn map_rect() -> Rect {
+    let size = screen_width().min(screen_height());
+    Rect::new(
+        (screen_width() - size) / 2.0,
+        (screen_height() - size) / 2.0,
+        size,
+        size,
+    )
+}
+
+fn to_angles(view: &Region, rect: &Rect, position: Vec2) -> (f64, f64) {
+    let u = ((position.x - rect.x) / rect.w - 0.5) as f64;
+    let v = ((position.y - rect.y) / rect.h - 0.5) as f64;
+    (view.center.0 + u * view.span, view.center.1 - v * view.span)
+}
+
+fn to_screen(view: &Region, rect: &Rect, angles: (f64, f64)) -> Vec2 {
+    let u = (angles.0 - view.center.0) / view.span + 0.5;
+    let v = 0.5 - (angles.1 - view.center.1) / view.span;
+    vec2(rect.x + u as f32 * rect.w, rect.y + v as f32 * rect.h)
+}
+
+//draws a map of the region as seen from the view
+fn draw_map(texture: &Texture2D, region: &Region, view: &Region, rect: &Rect) {
+    let corner = (
+        region.center.0 - region.span / 2.0,
+        region.center.1 + region.span / 2.0,
+    );
+    let position = to_screen(view, rect, corner);
+    let size = rect.w * (region.span / view.span) as f32;
+
+    draw_texture_ex(
+        texture,
+        position.x,
+        position.y,
+        WHITE,
+        DrawTextureParams {
+            dest_size: Some(vec2(size, size)),
+            ..Default::default()
+        },
+    );
+}
+
+fn texture(job: &FlipJob) -> Texture2D {
+    let texture = Texture2D::from_image(&job.image());
+    texture.set_filter(FilterMode::Nearest);
+    texture
+}
+
+#[macroquad::main("Double Pendulum Flip Fractal")]
+async fn main() {
+    let mut settings = FlipSettings::default();
+    let mut resolution = 200;
+    let mut view = Region::default();
+
+    let mut job = FlipJob::start(settings.clone(), view, resolution);
+    let mut current = texture(&job);
+    //last finished map, shown under the one being computed
+    let mut previous: Option<(Texture2D, Region)> = None;
+    let mut shown_progress = 0.0;
+
+    let mut drag: Option<(Vec2, Region)> = None;
+    let mut egui_wants_pointer = false;
+    let mut png_path = String::from("fractal.png");
+    let mut png_message = String::new();
+
+    loop {
+        let rect = map_rect();
+        let mouse = Vec2::from(mouse_position());
+
+        //zoom around the cursor, pan by dragging
+        if !egui_wants_pointer {
+            let wheel = mouse_wheel().1;
+            if wheel.abs() > 0.01 {
+                let anchor = to_angles(&view, &rect, mouse);
+                let factor = 1.25f64.powf(-wheel.signum() as f64);
+                view.span *= factor;
+                view.center = (
+                    anchor.0 + (view.center.0 - anchor.0) * factor,
+                    anchor.1 + (view.center.1 - anchor.1) * factor,
+                );
+            }
+            if is_mouse_button_pressed(MouseButton::Left) {
+                drag = Some((mouse, view));
+            }
+        }
+        if let Some((start, start_view)) = drag {
+            let moved = mouse - start;
+            view.center = (
+                start_view.center.0 - (moved.x / rect.w) as f64 * view.span,
+                start_view.center.1 + (moved.y / rect.h) as f64 * view.span,
+            );
+            if !is_mouse_button_down(MouseButton::Left) {
+                drag = None;
+            }
+        }
+
+        //the view settled somewhere else, compute it
+        if drag.is_none() && view != job.region {
+            if job.is_finished() {
+                previous = Some((current.clone(), job.region));
+            }
+            job = FlipJob::start(settings.clone(), view, resolution);
+            current = texture(&job);
+            shown_progress = 0.0;
+        }
+        if job.progress() > shown_progress {
+            shown_progress = job.progress();
+            current.update(&job.image());
+        }
+
+        clear_background(DARKGRAY);
+        if let Some((texture, region)) = &previous {
+            draw_map(texture, region, &view, &rect);
+        }
+        draw_map(&current, &job.region, &view, &rect);
+
+        egui_macroquad::ui(|egui_ctx| {
+            egui_wants_pointer = egui_ctx.wants_pointer_input();
+
+            egui::Window::new("Flip fractal").show(egui_ctx, |ui| {
+                ui.label(
+                    "Time until either link of a double pendulum released from rest flips over",
+                );
+                ui.label(format!(
+                    "Center: {:.3}°, {:.3}°, span {:.4}°",
+                    meth::rad2deg(view.center.0),
+                    meth::rad2deg(view.center.1),
+                    meth::rad2deg(view.span)
+                ));
+                if rect.contains(mouse) && !egui_wants_pointer {
+                    let angles = to_angles(&view, &rect, mouse);
+                    ui.label(format!(
+                        "Cursor: {:.3}°, {:.3}°",
+                        meth::rad2deg(angles.0),
+                        meth::rad2deg(angles.1)
+                    ));
+                }
+                ui.add(egui::ProgressBar::new(job.progress()).show_percentage());
+
+                egui::CollapsingHeader::new("Settings").show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Resolution");
+                        ui.add(egui::Slider::new(&mut resolution, 32..=1024).logarithmic(true));
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Max time");
+                        ui.add(
+                            egui::Slider::new(&mut settings.max_time, 1.0..=100.0)
+                                .logarithmic(true)
+                                .suffix(" s"),
+                        );
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Step size");
+                        ui.add(
+                            egui::Slider::new(&mut settings.time_step, 0.0005..=0.05)
+                                .logarithmic(true),
+                        );
+                    });
+                    egui::ComboBox::from_label("Integrator")
+                        .selected_text(settings.integrator.name())
+                        .show_ui(ui, |ui| {
+                            for option in IntegratorKind::ALL {
+                                ui.selectable_value(
+                                    &mut settings.integrator,
+                                    option,
+                                    option.name(),
+                                );
+                            }
+                        });
+                    for i in 0..2 {
+                        ui.horizontal(|ui| {
+                            ui.label(format!("Link {}", i + 1));
+                            ui.add(
+                                egui::DragValue::new(&mut settings.lengths[i])
+                                    .speed(0.01)
+                                    .clamp_range(0.01..=f64::INFINITY)
+                                    .prefix("len: "),
+                            );
+                            ui.add(
+                                egui::DragValue::new(&mut settings.masses[i])
+                                    .speed(0.01)
+                                    .clamp_range(0.01..=f64::INFINITY)
+                                    .prefix("mass: "),
+                            );
+                        });
+                    }
+                    ui.horizontal(|ui| {
+                        ui.label("Gravity");
+                        ui.add(
+                            egui::DragValue::new(&mut settings.gravity)
+                                .speed(0.01)
+                                .clamp_range(0.0..=f64::INFINITY),
+                        );
+                    });
+                });
+
+                ui.horizontal(|ui| {
+                    if ui.button("Render").clicked() {
+                        previous = None;
+                        job = FlipJob::start(settings.clone(), view, resolution);
+                        current = texture(&job);
+                        shown_progress = 0.0;
+                    }
+                    if ui.button("Reset view").clicked() {
+                        view = Region::default();
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(&mut png_path);
+                    if ui.button("Save PNG").clicked() {
+                        png_message = match job.save_png(&png_path) {
+                            Ok(()) if job.is_finished() => format!("Saved {png_path}"),
+                            Ok(()) => format!("Saved {png_path}, still unfinished"),
+                            Err(err) => format!("Could not save {png_path}: {err}"),
+                        };
+                    }
+                });
+                if !png_message.is_empty() {
+                    ui.label(&png_message);
+                }
+            });
+        });
+
+        egui_macroquad::draw();
+
+        next_frame().await;
+    }
+}
@@ -0,0 +1,845 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    process::exit,
+    sync::atomic::AtomicUsize,
+};
+
+use fyr_pendulum_too::{
+    collision::Obstacle,
+    constraint::{Constraint, Endpoint, Stabilization},
+    dulum::{Dulum, Parent},
+    expression::Expression,
+    gravity::GravityField,
+    integrator::IntegratorKind,
+    meth::deg2rad,
+    phase::{Coordinate, Quantity},
+    pivot::{Pivot, PivotMotion},
+    presets::{PendulumWave, Preset},
+    scene::Scene,
+    section::{Direction, Section},
+    simulation::{Simulation, DULUMS_COLORS},
+    solver::SolverKind,
+    spring::Spring,
+    sweep::{self, Parameter, Sampling, Sweep},
+};
+
+const USAGE: &str = "\
+Simulates a chain of dulums without a window and writes every step as CSV.
+
+Usage: headless [OPTIONS] --dulum SPEC [--dulum SPEC ...]
+
+Options:
+  --dulum SPEC       adds a dulum to the end of the chain, SPEC is a comma separated
+                     list of key=value pairs: angle (degrees), angle_der (degrees/s),
+                     len, len_der, mass, hardness, default_len, joint_friction,
+                     linear_drag, quadratic_drag, spring_damping and the flags
+                     elastic and push, parent is the number of the dulum it hangs from
+                     (0 for the pivot, default the previous one), anchor_x and anchor_y
+                     move a dulum hanging from the pivot away from it,
+                     e.g. angle=90,len=2,mass=1,elastic,hardness=100
+  --steps N          number of steps to simulate (default 10000)
+  --time-step DT     size of one step in seconds (default 0.001)
+  --integrator NAME  euler, semi-implicit-euler, leapfrog (default), velocity-verlet,
+                     rk4, yoshida4 or dopri5 (adaptive, substeps within --time-step)
+  --solver NAME      dense (default) or recursive, which is linear in the number of
+                     dulums and pays off for long chains
+  --atol TOL         absolute tolerance of dopri5 (default 1e-8 or the one of the scene)
+  --rtol TOL         relative tolerance of dopri5 (default 1e-8 or the one of the scene)
+  --every N          write only every N-th step (default 1)
+  --output FILE      file to write the CSV into (default stdout)
+  --pivot-x EXPR     moves the pivot along x as a function of time t,
+                     e.g. '0.5 * sin(2 * pi * t)'
+  --pivot-y EXPR     same for y, which points down
+  --pivot-vertical A,F
+                     oscillates the pivot up and down with amplitude A and frequency F
+  --pivot-horizontal A,F
+                     same from side to side
+  --gravity G        uniform gravity of G m/s^2 (default 9.8)
+  --gravity-angle A  direction of the uniform gravity in degrees from straight down
+  --preset NAME      starts from a built-in chain: small-angle, double, triple,
+                     swinging-spring, push-chain or six-chain
+  --wave SPEC        starts from a row of pendulums making a pendulum wave, SPEC keys:
+                     count, cycle (seconds until they line up again), oscillations
+                     (of the longest one per cycle), spacing and amplitude (degrees),
+                     e.g. count=15,cycle=60,oscillations=20
+  --scene FILE       starts from a scene saved by the viewer (.ron or .json),
+                     further --dulum options are appended to its chain
+  --config FILE      reads further options from FILE, whitespace separated,
+                     everything after # on a line is ignored
+  --section SPEC     records a Poincaré section, SPEC is a comma separated list of
+                     key=value pairs: surface, level (degrees for angles),
+                     direction (increasing, decreasing or both), x and y,
+                     coordinates are named like the CSV columns, e.g.
+                     surface=angle_1,level=0,x=angle_2,y=angle_der_2
+  --section-output FILE
+                     file to write the section CSV into (default section.csv)
+  --rod SPEC         adds a rod between two bobs or a bob and a fixed point, SPEC keys:
+                     a and b (bobs numbered from 1), x and y (the fixed point instead
+                     of b) and length (default the distance at the start),
+                     e.g. a=2,x=1.5,y=2 or a=1,b=3,length=2
+  --pin N            holds bob N where it starts
+  --spring SPEC      adds a spring between two bobs or a bob and a fixed point, SPEC keys:
+                     a, b, x and y like --rod, hardness (default 100), default_len
+                     (default the distance at the start), damping and the flag push
+                     which lets it go slack instead of pushing, e.g. a=1,b=2,hardness=20
+  --collide-bobs     bobs bounce off each other, their size is the radius
+  --obstacle SPEC    adds something for the bobs to bounce off: wall=X, floor=Y
+                     or circle=X:Y:RADIUS, e.g. floor=2.5
+  --restitution E    part of the speed the bobs bounce back with (default 0.8)
+  --baumgarte A,B    alpha and beta of the Baumgarte stabilization (default 5,5),
+                     'off' turns it off
+  --projection on|off
+                     projection back onto rods and pins after every step (default on)
+  --sweep SPEC       instead of the CSV of steps writes a bifurcation diagram, the chain
+                     is run for count values of one parameter on all cores and the
+                     sample coordinate is recorded after the transient at the crossings
+                     of the --section surface (default angle_1 increasing through 0)
+                     or every strobe seconds, SPEC keys: parameter (amplitude, gravity,
+                     mass_N, hardness_N or default_len_N), from, to, count, transient,
+                     duration (seconds), sample and strobe, e.g.
+                     parameter=amplitude,from=0,to=0.5,count=200,sample=angle_1,strobe=0.1
+  --help             prints this message
+";
+
+struct Config {
+    //chain, world and settings, a scene replaces it whole and the options change it
+    simulation: Simulation,
+    steps: usize,
+    //tolerances from the options win over the scene wherever they are given
+    absolute_tolerance: Option<f64>,
+    relative_tolerance: Option<f64>,
+    every: usize,
+    output: Option<String>,
+    section: Option<Section>,
+    section_output: String,
+    sweep: Option<SweepSpec>,
+    //rods and pins which take their length or position from the start
+    rods: Vec<(Endpoint, Endpoint, Option<f64>)>,
+    pins: Vec<usize>,
+    //springs which take their default length from the start unless it is given
+    new_springs: Vec<(Spring, Option<f64>)>,
+}
+
+struct SweepSpec {
+    parameter: Parameter,
+    from: f64,
+    to: f64,
+    count: usize,
+    transient: f64,
+    duration: f64,
+    sample: Coordinate,
+    strobe: Option<f64>,
+}
+
+fn parse_dulum(spec: &str, index: usize) -> Result<Dulum, String> {
+    let color = DULUMS_COLORS[index % DULUMS_COLORS.len()];
+    let mut dulum = Dulum::new(0.0, 2.0, 1.0, false, 100.0, 2.0, color, 0.2);
+
+    for pair in spec.split(',').map(str::trim).filter(|x| !x.is_empty()) {
+        let (key, value) = match pair.split_once('=') {
+            Some((key, value)) => (key.trim(), Some(value.trim())),
+            None => (pair, None),
+        };
+
+        let number = || -> Result<f64, String> {
+            let value = value.ok_or_else(|| format!("dulum key '{key}' needs a value"))?;
+            value
+                .parse::<f64>()
+                .map_err(|_| format!("invalid number '{value}' for dulum key '{key}'"))
+        };
+
+        match key {
+            "angle" => dulum.angle = deg2rad(number()?),
+            "angle_der" => dulum.angle_der = deg2rad(number()?),
+            "len" => dulum.len = number()?,
+            "len_der" => dulum.len_der = number()?,
+            "mass" => dulum.mass = number()?,
+            "hardness" => dulum.hardness = number()?,
+            "default_len" => dulum.default_len = number()?,
+            "joint_friction" => dulum.joint_friction = number()?,
+            "linear_drag" => dulum.linear_drag = number()?,
+            "quadratic_drag" => dulum.quadratic_drag = number()?,
+            "spring_damping" => dulum.spring_damping = number()?,
+            "elastic" => dulum.elastic = true,
+            "push" => dulum.push_elastic = true,
+            "anchor_x" => dulum.anchor.0 = number()?,
+            "anchor_y" => dulum.anchor.1 = number()?,
+            "parent" => {
+                let value = value.ok_or_else(|| "dulum key 'parent' needs a value".to_string())?;
+                dulum.parent = match value.parse::<usize>() {
+                    Ok(0) => Parent::Pivot,
+                    Ok(parent) if parent <= index => Parent::Dulum(parent - 1),
+                    _ => {
+                        return Err(format!(
+                            "parent '{value}' has to be 0 (the pivot) or a dulum before #{}",
+                            index + 1
+                        ))
+                    }
+                };
+            }
+            _ => return Err(format!("unknown dulum key '{key}'")),
+        }
+    }
+
+    Ok(dulum)
+}
+
+fn parse_section(spec: &str) -> Result<Section, String> {
+    let mut section = Section::new(
+        Coordinate::new(0, Quantity::Angle),
+        Coordinate::new(1, Quantity::Angle),
+        Coordinate::new(1, Quantity::AngleDer),
+    );
+    let mut level = 0.0;
+
+    for pair in spec.split(',').map(str::trim).filter(|x| !x.is_empty()) {
+        let (key, value) = pair
+            .split_once('=')
+            .map(|(key, value)| (key.trim(), value.trim()))
+            .ok_or_else(|| format!("section key '{pair}' needs a value"))?;
+
+        let coordinate = || {
+            Coordinate::from_key(value)
+                .ok_or_else(|| format!("invalid coordinate '{value}' for section key '{key}'"))
+        };
+
+        match key {
+            "surface" => section.surface = coordinate()?,
+            "x" => section.x = coordinate()?,
+            "y" => section.y = coordinate()?,
+            "level" => {
+                level = value
+                    .parse()
+                    .map_err(|_| format!("invalid number '{value}' for section key 'level'"))?;
+            }
+            "direction" => {
+                section.direction = Direction::ALL
+                    .into_iter()
+                    .find(|x| x.name() == value)
+                    .ok_or_else(|| format!("unknown section direction '{value}'"))?;
+            }
+            _ => return Err(format!("unknown section key '{key}'")),
+        }
+    }
+
+    section.level = if section.surface.quantity == Quantity::Angle {
+        deg2rad(level)
+    } else {
+        level
+    };
+
+    Ok(section)
+}
+
+fn parse_wave(spec: &str) -> Result<PendulumWave, String> {
+    let mut wave = PendulumWave::default();
+
+    for pair in spec.split(',').map(str::trim).filter(|x| !x.is_empty()) {
+        let (key, value) = pair
+            .split_once('=')
+            .map(|(key, value)| (key.trim(), value.trim()))
+            .ok_or_else(|| format!("wave key '{pair}' needs a value"))?;
+
+        let number = || {
+            value
+                .parse::<f64>()
+                .map_err(|_| format!("invalid number '{value}' for wave key '{key}'"))
+        };
+
+        match key {
+            "count" => {
+                wave.count = value
+                    .parse()
+                    .map_err(|_| format!("invalid whole number '{value}' for wave key 'count'"))?;
+            }
+            "cycle" => wave.cycle = number()?,
+            "oscillations" => wave.oscillations = number()?,
+            "spacing" => wave.spacing = number()?,
+            "amplitude" => wave.amplitude = number()?,
+            _ => return Err(format!("unknown wave key '{key}'")),
+        }
+    }
+
+    if wave.cycle <= 0.0 || wave.oscillations <= 0.0 {
+        return Err("wave cycle and oscillations have to be positive".to_string());
+    }
+
+    Ok(wave)
+}
+
+fn parse_sweep(spec: &str) -> Result<SweepSpec, String> {
+    let mut sweep = SweepSpec {
+        parameter: Parameter::DrivingAmplitude,
+        from: 0.0,
+        to: 1.0,
+        count: 100,
+        transient: 50.0,
+        duration: 100.0,
+        sample: Coordinate::new(0, Quantity::Angle),
+        strobe: None,
+    };
+
+    for pair in spec.split(',').map(str::trim).filter(|x| !x.is_empty()) {
+        let (key, value) = pair
+            .split_once('=')
+            .map(|(key, value)| (key.trim(), value.trim()))
+            .ok_or_else(|| format!("sweep key '{pair}' needs a value"))?;
+
+        let number = || {
+            value
+                .parse::<f64>()
+                .map_err(|_| format!("invalid number '{value}' for sweep key '{key}'"))
+        };
+
+        match key {
+            "parameter" => {
+                sweep.parameter = Parameter::from_key(value)
+                    .ok_or_else(|| format!("unknown sweep parameter '{value}'"))?;
+            }
+            "from" => sweep.from = number()?,
+            "to" => sweep.to = number()?,
+            "count" => {
+                sweep.count = value
+                    .parse()
+                    .map_err(|_| format!("invalid whole number '{value}' for sweep key 'count'"))?;
+            }
+            "transient" => sweep.transient = number()?,
+            "duration" => sweep.duration = number()?,
+            "sample" => {
+                sweep.sample = Coordinate::from_key(value).ok_or_else(|| {
+                    format!("invalid coordinate '{value}' for sweep key 'sample'")
+                })?;
+            }
+            "strobe" => sweep.strobe = Some(number()?),
+            _ => return Err(format!("unknown sweep key '{key}'")),
+        }
+    }
+
+    Ok(sweep)
+}
+
+fn parse_rod(spec: &str) -> Result<(Endpoint, Endpoint, Option<f64>), String> {
+    let (mut a, mut b, mut x, mut y, mut length) = (None, None, None, None, None);
+
+    for pair in spec.split(',').map(str::trim).filter(|x| !x.is_empty()) {
+        let (key, value) = pair
+            .split_once('=')
+            .map(|(key, value)| (key.trim(), value.trim()))
+            .ok_or_else(|| format!("rod key '{pair}' needs a value"))?;
+
+        let number = || {
+            value
+                .parse::<f64>()
+                .map_err(|_| format!("invalid number '{value}' for rod key '{key}'"))
+        };
+        let bob = || {
+            value
+                .parse::<usize>()
+                .ok()
+                .and_then(|bob| bob.checked_sub(1))
+                .ok_or_else(|| format!("invalid bob '{value}' for rod key '{key}'"))
+        };
+
+        match key {
+            "a" => a = Some(bob()?),
+            "b" => b = Some(bob()?),
+            "x" => x = Some(number()?),
+            "y" => y = Some(number()?),
+            "length" => length = Some(number()?),
+            _ => return Err(format!("unknown rod key '{key}'")),
+        }
+    }
+
+    let (a, b) = endpoints(a, b, x, y, "rod")?;
+    Ok((a, b, length))
+}
+
+//the bob a and either the bob b or the fixed point x, y
+fn endpoints(
+    a: Option<usize>,
+    b: Option<usize>,
+    x: Option<f64>,
+    y: Option<f64>,
+    what: &str,
+) -> Result<(Endpoint, Endpoint), String> {
+    let a = Endpoint::Bob(a.ok_or_else(|| format!("{what} needs the bob a"))?);
+    let b = match (b, x, y) {
+        (Some(b), None, None) => Endpoint::Bob(b),
+        (None, Some(x), Some(y)) => Endpoint::Fixed(x, y),
+        _ => return Err(format!("{what} needs either the bob b or both x and y")),
+    };
+
+    Ok((a, b))
+}
+
+fn parse_spring(spec: &str) -> Result<(Spring, Option<f64>), String> {
+    let (mut a, mut b, mut x, mut y, mut default_len) = (None, None, None, None, None);
+    let (mut hardness, mut damping, mut push_elastic) = (100.0, 0.0, false);
+
+    for pair in spec.split(',').map(str::trim).filter(|x| !x.is_empty()) {
+        if pair == "push" {
+            push_elastic = true;
+            continue;
+        }
+        let (key, value) = pair
+            .split_once('=')
+            .map(|(key, value)| (key.trim(), value.trim()))
+            .ok_or_else(|| format!("spring key '{pair}' needs a value"))?;
+
+        let number = || {
+            value
+                .parse::<f64>()
+                .map_err(|_| format!("invalid number '{value}' for spring key '{key}'"))
+        };
+        let bob = || {
+            value
+                .parse::<usize>()
+                .ok()
+                .and_then(|bob| bob.checked_sub(1))
+                .ok_or_else(|| format!("invalid bob '{value}' for spring key '{key}'"))
+        };
+
+        match key {
+            "a" => a = Some(bob()?),
+            "b" => b = Some(bob()?),
+            "x" => x = Some(number()?),
+            "y" => y = Some(number()?),
+            "hardness" => hardness = number()?,
+            "default_len" => default_len = Some(number()?),
+            "damping" => damping = number()?,
+            _ => return Err(format!("unknown spring key '{key}'")),
+        }
+    }
+
+    let (a, b) = endpoints(a, b, x, y, "spring")?;
+    let mut spring = Spring::new(a, b, hardness, default_len.unwrap_or_default());
+    spring.damping = damping;
+    spring.push_elastic = push_elastic;
+
+    Ok((spring, default_len))
+}
+
+fn parse_obstacle(spec: &str) -> Result<Obstacle, String> {
+    let (kind, value) = spec
+        .split_once('=')
+        .map(|(kind, value)| (kind.trim(), value.trim()))
+        .ok_or_else(|| format!("obstacle '{spec}' needs a value"))?;
+    let numbers = value
+        .split(':')
+        .map(|x| x.trim().parse::<f64>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| format!("invalid numbers '{value}' for obstacle '{kind}'"))?;
+
+    match (kind, numbers.as_slice()) {
+        ("wall", [x]) => Ok(Obstacle::Wall { x: *x }),
+        ("floor", [y]) => Ok(Obstacle::Floor { y: *y }),
+        ("circle", [x, y, radius]) => Ok(Obstacle::Circle {
+            center: (*x, *y),
+            radius: *radius,
+        }),
+        ("wall" | "floor", _) => Err(format!("obstacle '{kind}' needs one number")),
+        ("circle", _) => Err("obstacle 'circle' needs X:Y:RADIUS".to_string()),
+        _ => Err(format!("unknown obstacle '{kind}'")),
+    }
+}
+
+fn parse_args(args: Vec<String>, config: &mut Config) -> Result<(), String> {
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{arg} needs a value"));
+
+        match arg.as_str() {
+            "--dulum" => {
+                let dulum = parse_dulum(&value()?, config.simulation.dulums.len())?;
+                config.simulation.dulums.push(dulum);
+            }
+            "--steps" => {
+                config.steps = value()?
+                    .parse()
+                    .map_err(|_| "--steps needs a whole number".to_string())?;
+            }
+            "--time-step" => {
+                config.simulation.time_step = value()?
+                    .parse()
+                    .map_err(|_| "--time-step needs a number".to_string())?;
+            }
+            "--integrator" => {
+                let kind = match value()?.as_str() {
+                    "euler" => IntegratorKind::Euler,
+                    "semi-implicit-euler" => IntegratorKind::SemiImplicitEuler,
+                    "leapfrog" => IntegratorKind::Leapfrog,
+                    "velocity-verlet" => IntegratorKind::VelocityVerlet,
+                    "rk4" => IntegratorKind::RungeKutta4,
+                    "yoshida4" => IntegratorKind::Yoshida4,
+                    "dopri5" => IntegratorKind::DormandPrince,
+                    other => return Err(format!("unknown integrator '{other}'")),
+                };
+                config.simulation.integrator = kind.build();
+            }
+            "--solver" => {
+                config.simulation.solver = match value()?.as_str() {
+                    "dense" => SolverKind::Dense,
+                    "recursive" => SolverKind::Recursive,
+                    other => return Err(format!("unknown solver '{other}'")),
+                };
+            }
+            "--atol" => {
+                let tolerance = value()?
+                    .parse()
+                    .map_err(|_| "--atol needs a number".to_string())?;
+                config.absolute_tolerance = Some(tolerance);
+            }
+            "--rtol" => {
+                let tolerance = value()?
+                    .parse()
+                    .map_err(|_| "--rtol needs a number".to_string())?;
+                config.relative_tolerance = Some(tolerance);
+            }
+            "--every" => {
+                config.every = value()?
+                    .parse()
+                    .map_err(|_| "--every needs a whole number".to_string())?;
+            }
+            "--output" => config.output = Some(value()?),
+            "--section" => config.section = Some(parse_section(&value()?)?),
+            "--section-output" => config.section_output = value()?,
+            "--rod" => {
+                let rod = parse_rod(&value()?)?;
+                config.rods.push(rod);
+            }
+            "--pin" => {
+                let bob = value()?
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|bob| bob.checked_sub(1))
+                    .ok_or_else(|| "--pin needs a bob number from 1".to_string())?;
+                config.pins.push(bob);
+            }
+            "--spring" => {
+                let spring = parse_spring(&value()?)?;
+                config.new_springs.push(spring);
+            }
+            "--collide-bobs" => config.simulation.collisions.bobs = true,
+            "--obstacle" => {
+                let obstacle = parse_obstacle(&value()?)?;
+                config.simulation.collisions.obstacles.push(obstacle);
+            }
+            "--restitution" => {
+                config.simulation.collisions.restitution = value()?
+                    .parse()
+                    .map_err(|_| "--restitution needs a number".to_string())?;
+            }
+            "--baumgarte" => {
+                let value = value()?;
+                if value == "off" {
+                    config.simulation.stabilization.baumgarte = false;
+                } else {
+                    let (alpha, beta) = value
+                        .split_once(',')
+                        .and_then(|(a, b)| Some((a.trim().parse().ok()?, b.trim().parse().ok()?)))
+                        .ok_or_else(|| {
+                            format!("--baumgarte needs ALPHA,BETA or off, got '{value}'")
+                        })?;
+                    config.simulation.stabilization = Stabilization {
+                        baumgarte: true,
+                        alpha,
+                        beta,
+                        ..config.simulation.stabilization
+                    };
+                }
+            }
+            "--projection" => {
+                config.simulation.stabilization.projection = match value()?.as_str() {
+                    "on" => true,
+                    "off" => false,
+                    other => return Err(format!("--projection needs on or off, got '{other}'")),
+                };
+            }
+            "--sweep" => config.sweep = Some(parse_sweep(&value()?)?),
+            "--wave" => {
+                let wave = parse_wave(&value()?)?;
+                config.simulation.dulums =
+                    wave.dulums(config.simulation.gravity.magnitude_at(0.0, 0.0));
+            }
+            "--pivot-x" | "--pivot-y" => {
+                let expression =
+                    Expression::parse(&value()?).map_err(|err| format!("invalid {arg}: {err}"))?;
+                let (mut x, mut y) = match &config.simulation.pivot.motion {
+                    PivotMotion::Expression { x, y } => (x.clone(), y.clone()),
+                    _ => (Expression::constant(0.0), Expression::constant(0.0)),
+                };
+                if arg == "--pivot-x" {
+                    x = expression;
+                } else {
+                    y = expression;
+                }
+                config.simulation.pivot = Pivot::new(PivotMotion::Expression { x, y });
+            }
+            "--pivot-vertical" | "--pivot-horizontal" => {
+                let value = value()?;
+                let (amplitude, frequency) = value
+                    .split_once(',')
+                    .and_then(|(a, f)| Some((a.trim().parse().ok()?, f.trim().parse().ok()?)))
+                    .ok_or_else(|| format!("{arg} needs AMPLITUDE,FREQUENCY, got '{value}'"))?;
+                config.simulation.pivot = Pivot::new(if arg == "--pivot-vertical" {
+                    PivotMotion::Vertical {
+                        amplitude,
+                        frequency,
+                    }
+                } else {
+                    PivotMotion::Horizontal {
+                        amplitude,
+                        frequency,
+                    }
+                });
+            }
+            "--gravity" | "--gravity-angle" => {
+                let number: f64 = value()?
+                    .parse()
+                    .map_err(|_| format!("{arg} needs a number"))?;
+                let (mut magnitude, mut direction) = match config.simulation.gravity {
+                    GravityField::Uniform {
+                        magnitude,
+                        direction,
+                    } => (magnitude, direction),
+                    _ => (9.8, 0.0),
+                };
+                if arg == "--gravity" {
+                    magnitude = number;
+                } else {
+                    direction = deg2rad(number);
+                }
+                config.simulation.gravity = GravityField::Uniform {
+                    magnitude,
+                    direction,
+                };
+            }
+            "--preset" => {
+                let name = value()?;
+                let preset =
+                    Preset::from_key(&name).ok_or_else(|| format!("unknown preset '{name}'"))?;
+                config.simulation.dulums =
+                    preset.dulums(config.simulation.gravity.magnitude_at(0.0, 0.0));
+            }
+            "--scene" => {
+                let path = value()?;
+                let scene = Scene::load(&path)
+                    .map_err(|err| format!("could not load scene '{path}': {err}"))?;
+                config.simulation = scene.to_simulation();
+            }
+            "--config" => {
+                let path = value()?;
+                let content = std::fs::read_to_string(&path)
+                    .map_err(|err| format!("could not read config '{path}': {err}"))?;
+                let args = content
+                    .lines()
+                    .map(|line| line.split('#').next().unwrap_or_default())
+                    .flat_map(str::split_whitespace)
+                    .map(str::to_string)
+                    .collect();
+                parse_args(args, config)?;
+            }
+            "--help" | "-h" => {
+                print!("{USAGE}");
+                exit(0);
+            }
+            _ => return Err(format!("unknown argument '{arg}'")),
+        }
+    }
+
+    Ok(())
+}
+
+fn write_header(out: &mut impl Write, count: usize) -> io::Result<()> {
+    write!(out, "step,time,pivot_x,pivot_y")?;
+    for i in 1..=count {
+        write!(
+            out,
+            ",angle_{i},angle_der_{i},len_{i},len_der_{i},x_{i},y_{i},pot_grav_{i},pot_elas_{i},kinet_{i}"
+        )?;
+    }
+    writeln!(out, ",total,dissipated,violation,collisions")
+}
+
+fn write_row(out: &mut impl Write, step: usize, simulation: &Simulation) -> io::Result<()> {
+    let (pivot_x, pivot_y) = simulation.pivot_kinematics().position;
+    write!(out, "{step},{},{pivot_x},{pivot_y}", simulation.time)?;
+
+    let positions = simulation.positions();
+    let energies = simulation.energies();
+    for ((dulum, (x, y)), energy) in simulation.dulums.iter().zip(positions).zip(energies) {
+        write!(
+            out,
+            ",{},{},{},{},{},{},{},{},{}",
+            dulum.angle,
+            dulum.angle_der,
+            dulum.len,
+            dulum.len_der,
+            x,
+            y,
+            energy.potential_gravity,
+            energy.potential_elastic,
+            energy.kinetic
+        )?;
+    }
+
+    writeln!(
+        out,
+        ",{},{},{},{}",
+        simulation.total_energy(),
+        simulation.dissipated,
+        simulation.constraint_violation(),
+        simulation.collisions.events
+    )
+}
+
+fn run(mut config: Config) -> io::Result<()> {
+    let out: Box<dyn Write> = match &config.output {
+        Some(path) => Box::new(File::create(path)?),
+        None => Box::new(io::stdout().lock()),
+    };
+    let mut out = BufWriter::new(out);
+
+    let mut simulation = config.simulation;
+    if let Some(control) = simulation.integrator.error_control() {
+        control.absolute_tolerance = config
+            .absolute_tolerance
+            .unwrap_or(control.absolute_tolerance);
+        control.relative_tolerance = config
+            .relative_tolerance
+            .unwrap_or(control.relative_tolerance);
+    }
+
+    let positions = simulation.positions();
+    let position = |endpoint: Endpoint| match endpoint {
+        Endpoint::Bob(bob) => positions.get(bob).copied(),
+        Endpoint::Fixed(x, y) => Some((x, y)),
+    };
+    for (a, b, length) in config.rods {
+        let length = length.or_else(|| {
+            let (a, b) = (position(a)?, position(b)?);
+            Some((a.0 - b.0).hypot(a.1 - b.1))
+        });
+        //rods on missing bobs are ignored by the simulation anyway
+        simulation.constraints.push(Constraint::Rod {
+            a,
+            b,
+            length: length.unwrap_or_default(),
+        });
+    }
+    for bob in config.pins {
+        if let Some(position) = position(Endpoint::Bob(bob)) {
+            simulation
+                .constraints
+                .push(Constraint::Pin { bob, position });
+        }
+    }
+    for (mut spring, default_len) in config.new_springs {
+        spring.default_len = default_len
+            .or_else(|| {
+                let (a, b) = (position(spring.a)?, position(spring.b)?);
+                Some((a.0 - b.0).hypot(a.1 - b.1))
+            })
+            .unwrap_or_default();
+        simulation.springs.push(spring);
+    }
+
+    if let Some(spec) = config.sweep {
+        let sampling = match spec.strobe {
+            Some(period) => Sampling::Stroboscopic { period },
+            None => Sampling::Section(config.section.unwrap_or_else(|| {
+                Section::new(
+                    Coordinate::new(0, Quantity::Angle),
+                    spec.sample,
+                    spec.sample,
+                )
+            })),
+        };
+        let sweep = Sweep {
+            base: simulation,
+            parameter: spec.parameter,
+            from: spec.from,
+            to: spec.to,
+            count: spec.count,
+            transient: spec.transient,
+            duration: spec.duration,
+            coordinate: spec.sample,
+            sampling,
+        };
+
+        let results = sweep.run(&AtomicUsize::new(0)).map_err(io::Error::other)?;
+        sweep::write_csv(&mut out, sweep.parameter, sweep.coordinate, &results)?;
+        return out.flush();
+    }
+
+    write_header(&mut out, simulation.dulums.len())?;
+    write_row(&mut out, 0, &simulation)?;
+
+    for step in 1..=config.steps {
+        let stepped = match &mut config.section {
+            Some(section) => simulation
+                .advance_observed(simulation.time_step as f64, &mut |simulation| {
+                    section.observe(simulation)
+                }),
+            None => simulation.substep(),
+        };
+        //keep the rows up to the failure
+        if let Err(err) = stepped {
+            out.flush()?;
+            return Err(io::Error::other(format!(
+                "simulation stopped at step {step}: {err}"
+            )));
+        }
+
+        if step % config.every == 0 {
+            write_row(&mut out, step, &simulation)?;
+        }
+    }
+
+    out.flush()?;
+
+    if let Some(section) = &config.section {
+        section.save_csv(&config.section_output)?;
+    }
+
+    Ok(())
+}
+
+fn main() {
+    let mut config = Config {
+        simulation: Simulation::new(Vec::new()),
+        steps: 10000,
+        absolute_tolerance: None,
+        relative_tolerance: None,
+        every: 1,
+        output: None,
+        section: None,
+        section_output: String::from("section.csv"),
+        sweep: None,
+        rods: Vec::new(),
+        pins: Vec::new(),
+        new_springs: Vec::new(),
+    };
+
+    if let Err(err) = parse_args(std::env::args().skip(1).collect(), &mut config) {
+        eprintln!("error: {err}\n\n{USAGE}");
+        exit(1);
+    }
+
+    if config.simulation.dulums.is_empty() {
+        eprintln!("error: at least one --dulum is needed\n\n{USAGE}");
+        exit(1);
+    }
+    let time_step = config.simulation.time_step;
+    if !(time_step > 0.0 && time_step.is_finite()) || config.every == 0 {
+        eprintln!("error: --time-step and --every have to be positive");
+        exit(1);
+    }
+
+    if let Err(err) = run(config) {
+        eprintln!("error: {err}");
+        exit(1);
+    }
+}
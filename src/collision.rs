@@ -0,0 +1,218 @@
+use macroquad::prelude::*;
+use nalgebra::{DMatrix, DVector};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    dulum::Dulum,
+    pivot::Kinematics,
+    simulation::{accumulate_parents, accumulate_positions},
+};
+
+//bobs touching slower than this stop instead of bouncing, so resting ones don't count
+pub const REST_SPEED: f64 = 0.1;
+
+///Something static the bobs bounce off, y points down
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Obstacle {
+    ///Vertical line at x, bobs stay on the side they are on
+    Wall {
+        x: f64,
+    },
+    ///Horizontal line at y
+    Floor {
+        y: f64,
+    },
+    Circle {
+        center: (f64, f64),
+        radius: f64,
+    },
+}
+
+impl Obstacle {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Obstacle::Wall { .. } => "Wall",
+            Obstacle::Floor { .. } => "Floor",
+            Obstacle::Circle { .. } => "Circle",
+        }
+    }
+
+    ///Gap between the obstacle and a ball, negative if they overlap,
+    ///and the normal pointing from the obstacle to the ball
+    pub fn contact(&self, (x, y): (f64, f64), radius: f64) -> (f64, (f64, f64)) {
+        match *self {
+            Obstacle::Wall { x: wall } => (
+                (x - wall).abs() - radius,
+                (if x < wall { -1.0 } else { 1.0 }, 0.0),
+            ),
+            Obstacle::Floor { y: floor } => (
+                (y - floor).abs() - radius,
+                (0.0, if y < floor { -1.0 } else { 1.0 }),
+            ),
+            Obstacle::Circle {
+                center,
+                radius: size,
+            } => {
+                let (dx, dy) = (x - center.0, y - center.1);
+                let distance = dx.hypot(dy);
+                //right in the middle, push it up
+                let normal = if distance > 0.0 {
+                    (dx / distance, dy / distance)
+                } else {
+                    (0.0, -1.0)
+                };
+                (distance - size - radius, normal)
+            }
+        }
+    }
+
+    pub fn render(&self) {
+        //long enough to look endless
+        const FAR: f32 = 1000.0;
+
+        match *self {
+            Obstacle::Wall { x } => draw_line(x as f32, -FAR, x as f32, FAR, 0.05, DARKGRAY),
+            Obstacle::Floor { y } => draw_line(-FAR, y as f32, FAR, y as f32, 0.05, DARKGRAY),
+            Obstacle::Circle { center, radius } => draw_circle_lines(
+                center.0 as f32,
+                center.1 as f32,
+                radius as f32,
+                0.05,
+                DARKGRAY,
+            ),
+        }
+    }
+}
+
+///What the bobs collide with and how they bounce
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Collisions {
+    ///Bobs bounce off each other, their size is the radius
+    pub bobs: bool,
+    ///Part of the approaching speed they bounce back with, 1 loses no energy
+    pub restitution: f64,
+    pub obstacles: Vec<Obstacle>,
+    ///Bounces since the start
+    #[serde(skip)]
+    pub events: usize,
+}
+
+impl Default for Collisions {
+    fn default() -> Self {
+        Self {
+            bobs: false,
+            restitution: 0.8,
+            obstacles: Vec::new(),
+            events: 0,
+        }
+    }
+}
+
+impl Collisions {
+    pub fn is_active(&self) -> bool {
+        self.bobs || !self.obstacles.is_empty()
+    }
+}
+
+///Bob touching another bob or an obstacle
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Contact {
+    pub bob: usize,
+    ///The other bob, None for obstacles
+    pub other: Option<usize>,
+    ///How far apart they are, negative when they overlap
+    pub gap: f64,
+    ///Unit vector from the other one to the bob
+    pub normal: (f64, f64),
+}
+
+///Every overlap of the bobs with each other and with the obstacles, a bob never collides
+///with the one it hangs from or with one hanging from the same joint, those touch at rest
+pub fn find_contacts(
+    collisions: &Collisions,
+    dulums: &[Dulum],
+    pivot: &Kinematics,
+) -> Vec<Contact> {
+    let mut contacts = Vec::new();
+    if !collisions.is_active() {
+        return contacts;
+    }
+    let positions = accumulate_positions(dulums, pivot);
+    let parents = accumulate_parents(dulums);
+
+    //dulums from the pivot share a joint only if they have the same anchor
+    let joined = |a: usize, b: usize| {
+        parents[a] == Some(b)
+            || parents[b] == Some(a)
+            || (parents[a] == parents[b]
+                && (parents[a].is_some() || dulums[a].anchor == dulums[b].anchor))
+    };
+
+    for (bob, (dulum, position)) in dulums.iter().zip(&positions).enumerate() {
+        let radius = dulum.size as f64;
+
+        for obstacle in &collisions.obstacles {
+            let (gap, normal) = obstacle.contact(*position, radius);
+            if gap < 0.0 {
+                contacts.push(Contact {
+                    bob,
+                    other: None,
+                    gap,
+                    normal,
+                });
+            }
+        }
+
+        if !collisions.bobs {
+            continue;
+        }
+        for other in (bob + 1..dulums.len()).filter(|x| !joined(bob, *x)) {
+            let (dx, dy) = (
+                position.0 - positions[other].0,
+                position.1 - positions[other].1,
+            );
+            let distance = dx.hypot(dy);
+            let gap = distance - radius - dulums[other].size as f64;
+            //bobs right on top of each other have no normal
+            if gap < 0.0 && distance > 0.0 {
+                contacts.push(Contact {
+                    bob,
+                    other: Some(other),
+                    gap,
+                    normal: (dx / distance, dy / distance),
+                });
+            }
+        }
+    }
+
+    contacts
+}
+
+///Rows of the jacobi matrix along the normals of the contacts,
+///jacobi has two rows per bob like accumulate_jacobi
+pub fn contact_jacobian(contacts: &[Contact], jacobi: &DMatrix<f64>) -> DMatrix<f64> {
+    let mut result = DMatrix::zeros(contacts.len(), jacobi.ncols());
+
+    for (row, contact) in contacts.iter().enumerate() {
+        let (nx, ny) = contact.normal;
+        let mut along = jacobi.row(contact.bob * 2) * nx + jacobi.row(contact.bob * 2 + 1) * ny;
+        if let Some(other) = contact.other {
+            along -= jacobi.row(other * 2) * nx + jacobi.row(other * 2 + 1) * ny;
+        }
+        result.set_row(row, &along);
+    }
+
+    result
+}
+
+///Speed the bobs of the contacts move apart with, velocities are absolute ones of the bobs
+pub fn separation_speeds(contacts: &[Contact], velocities: &[(f64, f64)]) -> DVector<f64> {
+    DVector::from_iterator(
+        contacts.len(),
+        contacts.iter().map(|contact| {
+            let (vx, vy) = velocities[contact.bob];
+            let (ox, oy) = contact.other.map_or((0.0, 0.0), |other| velocities[other]);
+            (vx - ox) * contact.normal.0 + (vy - oy) * contact.normal.1
+        }),
+    )
+}
@@ -0,0 +1,273 @@
+use nalgebra::{DMatrix, DVector};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    dulum::Dulum,
+    meth::solve,
+    pivot::Kinematics,
+    simulation::{
+        accumulate_constraint, accumulate_jacobi, accumulate_positions, accumulate_state,
+    },
+};
+
+///End of a rod, either a bob (indexed from zero) or a point fixed in space
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Endpoint {
+    Bob(usize),
+    Fixed(f64, f64),
+}
+
+impl Endpoint {
+    pub fn label(&self) -> String {
+        match self {
+            Endpoint::Bob(bob) => format!("#{}", bob + 1),
+            Endpoint::Fixed(x, y) => format!("({x:.2}, {y:.2})"),
+        }
+    }
+
+    pub fn is_valid(&self, count: usize) -> bool {
+        match self {
+            Endpoint::Bob(bob) => *bob < count,
+            Endpoint::Fixed(..) => true,
+        }
+    }
+}
+
+///Extra constraint on the bobs on top of the chain itself, solved with Lagrange multipliers
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Constraint {
+    ///Keeps the endpoints at length from each other, closes loops between bobs
+    Rod {
+        a: Endpoint,
+        b: Endpoint,
+        length: f64,
+    },
+    ///Holds the bob at a position
+    Pin { bob: usize, position: (f64, f64) },
+}
+
+impl Constraint {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Constraint::Rod { .. } => "Rod",
+            Constraint::Pin { .. } => "Pin",
+        }
+    }
+
+    ///Constraints on bobs which don't exist are ignored
+    pub fn is_valid(&self, count: usize) -> bool {
+        match self {
+            Constraint::Rod { a, b, .. } => a.is_valid(count) && b.is_valid(count) && a != b,
+            Constraint::Pin { bob, .. } => *bob < count,
+        }
+    }
+
+    fn rows(&self) -> usize {
+        match self {
+            Constraint::Rod { .. } => 1,
+            Constraint::Pin { .. } => 2,
+        }
+    }
+}
+
+///How the constraints are kept from drifting away
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Stabilization {
+    ///Feeds the violation back into the constraint accelerations
+    pub baumgarte: bool,
+    ///Damping of the velocity violation
+    pub alpha: f64,
+    ///Stiffness of the position violation
+    pub beta: f64,
+    ///Moves the state back onto the constraints after every step
+    pub projection: bool,
+}
+
+impl Default for Stabilization {
+    fn default() -> Self {
+        Self {
+            baumgarte: true,
+            alpha: 5.0,
+            beta: 5.0,
+            projection: true,
+        }
+    }
+}
+
+///Constraints linearized around the current state,
+///jacobian * accelerations + bias is the second derivative of value
+pub struct ConstraintRows {
+    pub jacobian: DMatrix<f64>,
+    pub bias: DVector<f64>,
+    pub value: DVector<f64>,
+    pub rate: DVector<f64>,
+}
+
+//position, velocity, acceleration not coming from the generalized accelerations
+//and rows of the jacobi matrix of one endpoint
+struct EndpointState {
+    position: (f64, f64),
+    velocity: (f64, f64),
+    bias: (f64, f64),
+    jacobi: DMatrix<f64>,
+}
+
+pub fn accumulate_constraint_rows(
+    constraints: &[Constraint],
+    dulums: &[Dulum],
+    pivot: &Kinematics,
+) -> ConstraintRows {
+    let count = dulums.len();
+    let constraints: Vec<_> = constraints.iter().filter(|x| x.is_valid(count)).collect();
+    let rows = constraints.iter().map(|x| x.rows()).sum();
+
+    let jacobi = accumulate_jacobi(dulums);
+    let dofs = jacobi.ncols();
+    let positions = accumulate_positions(dulums, pivot);
+    let velocities = &jacobi * accumulate_state(dulums, 0.0).velocities;
+    let curvature = accumulate_constraint(dulums);
+
+    let endpoint = |endpoint: Endpoint| match endpoint {
+        Endpoint::Bob(bob) => EndpointState {
+            position: positions[bob],
+            velocity: (
+                velocities[bob * 2] + pivot.velocity.0,
+                velocities[bob * 2 + 1] + pivot.velocity.1,
+            ),
+            bias: (
+                curvature[bob * 2] + pivot.acceleration.0,
+                curvature[bob * 2 + 1] + pivot.acceleration.1,
+            ),
+            jacobi: jacobi.rows(bob * 2, 2).into_owned(),
+        },
+        Endpoint::Fixed(x, y) => EndpointState {
+            position: (x, y),
+            velocity: (0.0, 0.0),
+            bias: (0.0, 0.0),
+            jacobi: DMatrix::zeros(2, dofs),
+        },
+    };
+
+    let mut result = ConstraintRows {
+        jacobian: DMatrix::zeros(rows, dofs),
+        bias: DVector::zeros(rows),
+        value: DVector::zeros(rows),
+        rate: DVector::zeros(rows),
+    };
+
+    let mut row = 0;
+    for constraint in constraints {
+        match *constraint {
+            //C = (d.d - length^2) / 2 where d goes from b to a
+            Constraint::Rod { a, b, length } => {
+                let (a, b) = (endpoint(a), endpoint(b));
+                let d = (a.position.0 - b.position.0, a.position.1 - b.position.1);
+                let v = (a.velocity.0 - b.velocity.0, a.velocity.1 - b.velocity.1);
+                let relative = a.jacobi - b.jacobi;
+
+                result
+                    .jacobian
+                    .set_row(row, &(relative.row(0) * d.0 + relative.row(1) * d.1));
+                result.bias[row] = v.0 * v.0
+                    + v.1 * v.1
+                    + d.0 * (a.bias.0 - b.bias.0)
+                    + d.1 * (a.bias.1 - b.bias.1);
+                result.value[row] = (d.0 * d.0 + d.1 * d.1 - length * length) / 2.0;
+                result.rate[row] = d.0 * v.0 + d.1 * v.1;
+            }
+            //C = position of the bob - position
+            Constraint::Pin { bob, position } => {
+                let bob = endpoint(Endpoint::Bob(bob));
+                for (i, (at, (velocity, bias))) in [
+                    (bob.position.0 - position.0, (bob.velocity.0, bob.bias.0)),
+                    (bob.position.1 - position.1, (bob.velocity.1, bob.bias.1)),
+                ]
+                .into_iter()
+                .enumerate()
+                {
+                    result.jacobian.set_row(row + i, &bob.jacobi.row(i));
+                    result.bias[row + i] = bias;
+                    result.value[row + i] = at;
+                    result.rate[row + i] = velocity;
+                }
+            }
+        }
+        row += constraint.rows();
+    }
+
+    result
+}
+
+///Violation of every valid constraint in meters
+pub fn accumulate_violation(
+    constraints: &[Constraint],
+    dulums: &[Dulum],
+    pivot: &Kinematics,
+) -> Vec<f64> {
+    let positions = accumulate_positions(dulums, pivot);
+    let position = |endpoint: Endpoint| match endpoint {
+        Endpoint::Bob(bob) => positions[bob],
+        Endpoint::Fixed(x, y) => (x, y),
+    };
+
+    constraints
+        .iter()
+        .filter(|x| x.is_valid(dulums.len()))
+        .map(|constraint| match *constraint {
+            Constraint::Rod { a, b, length } => {
+                let (a, b) = (position(a), position(b));
+                (a.0 - b.0).hypot(a.1 - b.1) - length
+            }
+            Constraint::Pin { bob, position } => {
+                let (x, y) = positions[bob];
+                (x - position.0).hypot(y - position.1)
+            }
+        })
+        .collect()
+}
+
+///Accelerations of the free system `mass * accelerations = forces` with the constraint forces added,
+///stabilization feeds the current violation back in, None if the mass matrix is singular
+pub fn constrained_accelerations(
+    mass: &DMatrix<f64>,
+    forces: &DVector<f64>,
+    rows: &ConstraintRows,
+    stabilization: &Stabilization,
+) -> Option<DVector<f64>> {
+    let free = solve(
+        mass,
+        &DMatrix::from_column_slice(forces.len(), 1, forces.as_slice()),
+    )?
+    .column(0)
+    .into_owned();
+    if rows.value.is_empty() {
+        return Some(free);
+    }
+
+    //wanted second derivative of the constraints
+    let mut target = -&rows.bias;
+    if stabilization.baumgarte {
+        target -= &rows.rate * (2.0 * stabilization.alpha)
+            + &rows.value * (stabilization.beta * stabilization.beta);
+    }
+
+    //redundant constraints make the system singular, pseudo inverse picks one solution
+    let response = solve(mass, &rows.jacobian.transpose())?;
+    let schur = &rows.jacobian * &response;
+    let multipliers = schur.pseudo_inverse(1e-12).ok()? * (target - &rows.jacobian * &free);
+
+    Some(free + response * multipliers)
+}
+
+///Change of the generalized coordinates or velocities which removes the violation,
+///weighted by the mass so light parts move more
+pub fn correction(
+    mass: &DMatrix<f64>,
+    jacobian: &DMatrix<f64>,
+    violation: &DVector<f64>,
+) -> Option<DVector<f64>> {
+    let response = solve(mass, &jacobian.transpose())?;
+    let schur = jacobian * &response;
+
+    Some(-(response * (schur.pseudo_inverse(1e-12).ok()? * violation)))
+}
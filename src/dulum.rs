@@ -3,13 +3,49 @@ use std::collections::VecDeque;
 use iterwindows::IterArrayWindows;
 use macroquad::prelude::*;
 use nalgebra::DVector;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    gravity::GravityField,
+    meth::{hooks_energy, hooks_force, spring_damping_force},
+};
+
+///What a dulum hangs from, dulums are indexed from zero
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Parent {
+    ///The dulum before it, the pivot for the first one, makes a serial chain
+    #[default]
+    Previous,
+    Pivot,
+    ///Bob of a dulum, which has to come before it
+    Dulum(usize),
+}
+
+impl Parent {
+    ///Index of the parent of the dulum at ind, None for the pivot,
+    ///parents which don't come before the dulum fall back to the previous one
+    pub fn index(&self, ind: usize) -> Option<usize> {
+        match *self {
+            Parent::Pivot => None,
+            Parent::Dulum(parent) if parent < ind => Some(parent),
+            _ => ind.checked_sub(1),
+        }
+    }
+}
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Dulum {
     pub visible: bool,
     pub visible_line: bool,
     pub visible_trace: bool,
 
+    #[serde(default)]
+    pub parent: Parent,
+    ///Where the dulum hangs relative to the pivot, only if it hangs from the pivot,
+    ///lets several chains share one world
+    #[serde(default)]
+    pub anchor: (f64, f64),
+
     pub angle: f64,
     pub len: f64,
     pub default_len: f64,
@@ -22,13 +58,26 @@ pub struct Dulum {
     pub push_elastic: bool,
     pub hardness: f64,
 
+    //dissipation
+    #[serde(default)]
+    pub joint_friction: f64,
+    #[serde(default)]
+    pub linear_drag: f64,
+    #[serde(default)]
+    pub quadratic_drag: f64,
+    #[serde(default)]
+    pub spring_damping: f64,
+
+    #[serde(with = "crate::scene::color")]
     pub color: Color,
     pub size: f32,
 
+    #[serde(skip)]
     trail: VecDeque<(f32, f32)>,
 }
 
 impl Dulum {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         angle: f64,
         len: f64,
@@ -43,6 +92,8 @@ impl Dulum {
             visible: true,
             visible_line: true,
             visible_trace: true,
+            parent: Parent::Previous,
+            anchor: (0.0, 0.0),
             angle,
             len,
             mass,
@@ -50,6 +101,10 @@ impl Dulum {
             len_der: 0.0,
             elastic,
             push_elastic: false,
+            joint_friction: 0.0,
+            linear_drag: 0.0,
+            quadratic_drag: 0.0,
+            spring_damping: 0.0,
             color,
             size,
             trail: VecDeque::with_capacity(1024),
@@ -85,11 +140,9 @@ impl Dulum {
 
 //meth part
 impl Dulum {
-    pub fn get_jacobi_vectors(
-        &self,
-        id: usize,
-        count: usize,
-    ) -> (DVector<f64>, Option<DVector<f64>>) {
+    ///Columns of the jacobi matrix, moved tells which bobs hang below the dulum, itself included
+    pub fn get_jacobi_vectors(&self, moved: &[bool]) -> (DVector<f64>, Option<DVector<f64>>) {
+        let count = moved.len();
         //create vectors
         let mut angle_vector = DVector::zeros(count * 2);
         let mut len_vector = if self.elastic {
@@ -103,7 +156,7 @@ impl Dulum {
         let len_cos = self.len * ang_cos;
         let len_sin = -self.len * ang_sin;
 
-        for i in id..count {
+        for i in (0..count).filter(|i| moved[*i]) {
             //fill angle_vector
             angle_vector[i * 2] = len_cos;
             angle_vector[i * 2 + 1] = len_sin;
@@ -134,11 +187,21 @@ impl Dulum {
         )
     }
 
-    pub fn get_partial_constraint(
-        &self,
-        id: usize,
-        count: usize,
-    ) -> (DVector<f64>, Option<DVector<f64>>) {
+    pub fn set_coordinates(&mut self, angle: f64, len: Option<f64>) {
+        self.angle = angle;
+        if let Some(len) = len {
+            self.len = len;
+        }
+    }
+
+    pub fn set_coordinates_der(&mut self, angle_der: f64, len_der: Option<f64>) {
+        self.angle_der = angle_der;
+        //pokud není elastický, vymaž len_der
+        self.len_der = len_der.unwrap_or(0.0);
+    }
+
+    pub fn get_partial_constraint(&self, moved: &[bool]) -> (DVector<f64>, Option<DVector<f64>>) {
+        let count = moved.len();
         //vytvoř vektory
         let mut angle_constraint = DVector::zeros(count * 2);
         let mut len_constraint = if self.elastic {
@@ -153,7 +216,7 @@ impl Dulum {
         let len_sin = -self.len * angle_sin * self.angle_der;
         let len_cos = -self.len * angle_cos * self.angle_der;
 
-        for i in id..count {
+        for i in (0..count).filter(|i| moved[*i]) {
             //fill angle_constraint
             if self.elastic {
                 angle_constraint[i * 2] = len_sin + angle_cos * self.len_der;
@@ -178,40 +241,40 @@ impl Dulum {
         (
             0.0,
             if self.elastic {
-                if self.push_elastic && self.len < self.default_len{
-                    Some(0.0)
-                }else{
-                    Some(-self.hardness * (self.len - self.default_len))
-                }
+                Some(hooks_force(
+                    self.hardness,
+                    self.default_len,
+                    self.push_elastic,
+                    self.len,
+                ))
             } else {
                 None
             },
         )
     }
-}
-
-//steppin and gettin
-impl Dulum {
-    pub fn leapfrog_part_one(&mut self, dt: f64, angle_der_der: f64, len_der_der: f64) {
-        //pokud není elastický, vymaž len_der
-        if !self.elastic {
-            self.len_der = 0.0;
-        }
-        //split in half for reasons
-        self.angle_der += angle_der_der * dt / 2.0;
-        self.len_der += len_der_der * dt / 2.0;
 
-        //increase only by half
-        self.angle += self.angle_der * dt;
-        self.len += self.len_der * dt;
+    ///Joint friction against the parent dulum and spring damping,
+    ///the parent gets the opposite of the angle part
+    pub fn damping_force(&self, previous_angle_der: f64) -> (f64, Option<f64>) {
+        (
+            -self.joint_friction * (self.angle_der - previous_angle_der),
+            if self.elastic {
+                Some(spring_damping_force(self.spring_damping, self.len_der))
+            } else {
+                None
+            },
+        )
     }
 
-    pub fn leapfrog_part_two(&mut self, dt: f64, angle_der_der: f64, len_der_der: f64) {
-        //increase velocities to newer values
-        self.angle_der += angle_der_der * dt / 2.0;
-        self.len_der += len_der_der * dt / 2.0;
+    ///Air drag on the bob moving with the absolute velocity (vx, vy)
+    pub fn drag_force(&self, vx: f64, vy: f64) -> (f64, f64) {
+        let coefficient = self.linear_drag + self.quadratic_drag * vx.hypot(vy);
+        (-coefficient * vx, -coefficient * vy)
     }
+}
 
+//steppin and gettin
+impl Dulum {
     pub fn add_trail(&mut self, previous_x: f32, previous_y: f32) -> (f32, f32) {
         if self.trail.len() >= 1024 {
             self.trail.pop_front();
@@ -224,6 +287,10 @@ impl Dulum {
         (x, y)
     }
 
+    pub fn clear_trail(&mut self) {
+        self.trail.clear();
+    }
+
     pub fn render_trail(&self) {
         if !self.visible_trace {
             return;
@@ -244,16 +311,47 @@ impl Dulum {
     pub fn is_elastic(&self) -> bool {
         self.elastic
     }
+
+    ///Turns the dulum so its bob lies on the line from its parent to the target,
+    ///elastic dulums also stretch to reach it
+    pub fn aim(&mut self, previous_x: f64, previous_y: f64, x: f64, y: f64) {
+        let (dx, dy) = (x - previous_x, y - previous_y);
+
+        self.angle = dx.atan2(dy);
+        if self.elastic {
+            self.len = dx.hypot(dy);
+        }
+        self.angle_der = 0.0;
+        self.len_der = 0.0;
+    }
+
+    ///Sets the derivatives so the bob moves with (vx, vy) relative to its parent,
+    ///rigid dulums only keep the part perpendicular to the line
+    pub fn set_relative_velocity(&mut self, vx: f64, vy: f64) {
+        let (sin, cos) = self.angle.sin_cos();
+
+        self.angle_der = (vx * cos - vy * sin) / self.len;
+        self.len_der = if self.elastic {
+            vx * sin + vy * cos
+        } else {
+            0.0
+        };
+    }
 }
 
 //Energy calculations
 impl Dulum {
     ///Returns (Energy, new_x, new_y)
-    pub fn potential_gravity_energy(&self, previous_x: f64, previous_y: f64) -> (f64, f64, f64) {
+    pub fn potential_gravity_energy(
+        &self,
+        previous_x: f64,
+        previous_y: f64,
+        gravity: &GravityField,
+    ) -> (f64, f64, f64) {
         let x = previous_x + (self.len * self.angle.sin());
         let y = previous_y + (self.len * self.angle.cos());
 
-        (- self.mass * 9.8 * y, x, y)
+        (self.mass * gravity.potential(x, y), x, y)
     }
 
     pub fn potential_elastic_energy(&self) -> f64 {
@@ -261,14 +359,18 @@ impl Dulum {
             return 0.0;
         }
 
-        if self.push_elastic && self.len < self.default_len {
-            0.0
-        }else{
-            self.hardness * (self.len - self.default_len).powi(2) / 2.0
-        }
+        hooks_energy(self.hardness, self.default_len, self.push_elastic, self.len)
     }
 
-    pub fn kinetic_energy(&self) -> f64 {
-        self.mass * (self.len_der.powi(2) + self.len.powi(2)*self.angle_der.powi(2) ) / 2.0
+    ///Takes the velocity of the previous bob, as the bob moves with all of its parents
+    ///Returns (Energy, new_vx, new_vy)
+    pub fn kinetic_energy(&self, previous_vx: f64, previous_vy: f64) -> (f64, f64, f64) {
+        let (sin, cos) = self.angle.sin_cos();
+        let len_der = if self.elastic { self.len_der } else { 0.0 };
+
+        let vx = previous_vx + len_der * sin + self.len * cos * self.angle_der;
+        let vy = previous_vy + len_der * cos - self.len * sin * self.angle_der;
+
+        (self.mass * (vx.powi(2) + vy.powi(2)) / 2.0, vx, vy)
     }
 }
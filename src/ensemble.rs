@@ -0,0 +1,94 @@
+use std::collections::VecDeque;
+
+use macroquad::prelude::*;
+
+use crate::simulation::{Simulation, SimulationError};
+
+//colors the copies get mixed with, so they stay apart from the original
+const TINTS: [Color; 6] = [SKYBLUE, LIME, PINK, GOLD, VIOLET, WHITE];
+
+///Copies of a chain that start with slightly different angle of one dulum,
+///stepped and drawn together with the original to show how fast they part
+pub struct Ensemble {
+    pub copies: Vec<Simulation>,
+    ///(time, log10 of the distance from the original) of every copy
+    pub divergence: Vec<VecDeque<[f64; 2]>>,
+}
+
+impl Ensemble {
+    ///Copy i gets the angle of the dulum turned by i * perturbation
+    pub fn new(simulation: &Simulation, count: usize, dulum: usize, perturbation: f64) -> Self {
+        let copies = (1..=count)
+            .map(|i| {
+                let mut copy = simulation.clone();
+                if let Some(dulum) = copy.dulums.get_mut(dulum) {
+                    dulum.angle += i as f64 * perturbation;
+                }
+
+                let tint = TINTS[(i - 1) % TINTS.len()];
+                for dulum in &mut copy.dulums {
+                    dulum.color = Color::new(
+                        (dulum.color.r + tint.r) / 2.0,
+                        (dulum.color.g + tint.g) / 2.0,
+                        (dulum.color.b + tint.b) / 2.0,
+                        dulum.color.a * 0.7,
+                    );
+                    dulum.clear_trail();
+                }
+                copy
+            })
+            .collect();
+
+        Self {
+            copies,
+            divergence: vec![VecDeque::new(); count],
+        }
+    }
+
+    ///Steps the copies by the same frame time as the original and records their divergence,
+    ///false once a copy stops like the simulation does, it wouldn't be comparable anymore
+    pub fn step(&mut self, dt: f32, original: &Simulation) -> Result<bool, SimulationError> {
+        for copy in &mut self.copies {
+            copy.pivot.target = original.pivot.target;
+            if !copy.step(dt)? {
+                return Ok(false);
+            }
+        }
+        self.record(original);
+        Ok(true)
+    }
+
+    fn record(&mut self, original: &Simulation) {
+        let reference = original.state();
+
+        for (copy, divergence) in self.copies.iter().zip(&mut self.divergence) {
+            let state = copy.state();
+            //chains of different length after an edit can't be compared
+            if state.coordinates.len() != reference.coordinates.len() {
+                continue;
+            }
+
+            let distance = (state.coordinates - &reference.coordinates).norm();
+            if distance > 0.0 {
+                if divergence.len() >= 4096 {
+                    divergence.pop_front();
+                }
+                divergence.push_back([original.time, distance.log10()]);
+            }
+        }
+    }
+
+    pub fn render(&mut self) {
+        for copy in &mut self.copies {
+            copy.render();
+        }
+    }
+
+    ///Color of the last bob of the copy, for plots
+    pub fn color(&self, copy: usize) -> Color {
+        self.copies[copy]
+            .dulums
+            .last()
+            .map_or(WHITE, |dulum| dulum.color)
+    }
+}
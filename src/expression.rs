@@ -0,0 +1,317 @@
+use std::fmt;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+///Small math expression of the time `t`, e.g. `0.5 * sin(2 * pi * t)`
+#[derive(Clone)]
+pub struct Expression {
+    source: String,
+    tree: Node,
+}
+
+#[derive(Clone, Debug)]
+enum Node {
+    Number(f64),
+    Time,
+    Negate(Box<Node>),
+    Binary(char, Box<Node>, Box<Node>),
+    Call(fn(f64) -> f64, Box<Node>),
+}
+
+impl Expression {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut parser = Parser {
+            chars: source.chars().collect(),
+            position: 0,
+        };
+
+        let tree = parser.sum()?;
+        parser.skip_whitespace();
+        if let Some(c) = parser.peek() {
+            return Err(format!("unexpected '{c}' at {}", parser.position + 1));
+        }
+
+        Ok(Self {
+            source: source.to_string(),
+            tree,
+        })
+    }
+
+    pub fn constant(value: f64) -> Self {
+        Self {
+            source: value.to_string(),
+            tree: Node::Number(value),
+        }
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn eval(&self, time: f64) -> f64 {
+        self.tree.eval(time)
+    }
+}
+
+impl fmt::Debug for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Expression({:?})", self.source)
+    }
+}
+
+impl Serialize for Expression {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.source.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Expression {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let source = String::deserialize(deserializer)?;
+        Expression::parse(&source).map_err(serde::de::Error::custom)
+    }
+}
+
+impl Node {
+    fn eval(&self, time: f64) -> f64 {
+        match self {
+            Node::Number(x) => *x,
+            Node::Time => time,
+            Node::Negate(x) => -x.eval(time),
+            Node::Binary(op, a, b) => {
+                let (a, b) = (a.eval(time), b.eval(time));
+                match op {
+                    '+' => a + b,
+                    '-' => a - b,
+                    '*' => a * b,
+                    '/' => a / b,
+                    _ => a.powf(b),
+                }
+            }
+            Node::Call(function, x) => function(x.eval(time)),
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Parser {
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.position += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn digits(&mut self) {
+        while self.peek().is_some_and(|c| c.is_ascii_digit() || c == '.') {
+            self.position += 1;
+        }
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(c) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    //sum = product (('+' | '-') product)*
+    fn sum(&mut self) -> Result<Node, String> {
+        let mut node = self.product()?;
+        loop {
+            let op = if self.eat('+') {
+                '+'
+            } else if self.eat('-') {
+                '-'
+            } else {
+                return Ok(node);
+            };
+            node = Node::Binary(op, Box::new(node), Box::new(self.product()?));
+        }
+    }
+
+    //product = unary (('*' | '/') unary)*
+    fn product(&mut self) -> Result<Node, String> {
+        let mut node = self.unary()?;
+        loop {
+            let op = if self.eat('*') {
+                '*'
+            } else if self.eat('/') {
+                '/'
+            } else {
+                return Ok(node);
+            };
+            node = Node::Binary(op, Box::new(node), Box::new(self.unary()?));
+        }
+    }
+
+    //unary = '-' unary | power
+    fn unary(&mut self) -> Result<Node, String> {
+        if self.eat('-') {
+            return Ok(Node::Negate(Box::new(self.unary()?)));
+        }
+        self.power()
+    }
+
+    //power = atom ('^' unary)?
+    fn power(&mut self) -> Result<Node, String> {
+        let base = self.atom()?;
+        if self.eat('^') {
+            return Ok(Node::Binary('^', Box::new(base), Box::new(self.unary()?)));
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<Node, String> {
+        self.skip_whitespace();
+        let start = self.position;
+
+        match self.peek() {
+            Some('(') => {
+                self.position += 1;
+                let node = self.sum()?;
+                if !self.eat(')') {
+                    return Err(format!("missing ')' for '(' at {}", start + 1));
+                }
+                Ok(node)
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                self.digits();
+                //exponent like 1e-3, a lone e after a number is left alone
+                if matches!(self.peek(), Some('e' | 'E')) {
+                    let sign = matches!(self.chars.get(self.position + 1), Some('+' | '-'));
+                    let after = self.position + 1 + sign as usize;
+                    if self.chars.get(after).is_some_and(char::is_ascii_digit) {
+                        self.position = after;
+                        self.digits();
+                    }
+                }
+                let text: String = self.chars[start..self.position].iter().collect();
+                text.parse()
+                    .map(Node::Number)
+                    .map_err(|_| format!("invalid number '{text}'"))
+            }
+            Some(c) if c.is_alphabetic() => {
+                while self.peek().is_some_and(char::is_alphanumeric) {
+                    self.position += 1;
+                }
+                let name: String = self.chars[start..self.position].iter().collect();
+                let function: fn(f64) -> f64 = match name.as_str() {
+                    "t" => return Ok(Node::Time),
+                    "pi" => return Ok(Node::Number(std::f64::consts::PI)),
+                    "e" => return Ok(Node::Number(std::f64::consts::E)),
+                    "sin" => f64::sin,
+                    "cos" => f64::cos,
+                    "tan" => f64::tan,
+                    "exp" => f64::exp,
+                    "ln" => f64::ln,
+                    "sqrt" => f64::sqrt,
+                    "abs" => f64::abs,
+                    _ => return Err(format!("unknown name '{name}'")),
+                };
+                if !self.eat('(') {
+                    return Err(format!("'{name}' needs its argument in parentheses"));
+                }
+                let argument = self.sum()?;
+                if !self.eat(')') {
+                    return Err(format!("missing ')' after the argument of '{name}'"));
+                }
+                Ok(Node::Call(function, Box::new(argument)))
+            }
+            Some(c) => Err(format!("unexpected '{c}' at {}", start + 1)),
+            None => Err("unexpected end of expression".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Expression;
+    use crate::pivot::{Pivot, PivotMotion};
+
+    fn eval(source: &str, time: f64) -> f64 {
+        Expression::parse(source).unwrap().eval(time)
+    }
+
+    fn assert_close(a: f64, b: f64, tolerance: f64) {
+        assert!((a - b).abs() <= tolerance, "{a} is not close to {b}");
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(eval("42", 0.0), 42.0);
+        assert_eq!(eval(".5", 0.0), 0.5);
+        assert_eq!(eval("1e-3", 0.0), 1e-3);
+        assert_eq!(eval("2E2", 0.0), 200.0);
+        assert_eq!(eval("1.5e+1", 0.0), 15.0);
+        //e on its own is still the constant
+        assert_close(eval("2 * e", 0.0), 2.0 * std::f64::consts::E, 1e-12);
+    }
+
+    #[test]
+    fn precedence_and_associativity() {
+        assert_eq!(eval("1 + 2 * 3", 0.0), 7.0);
+        assert_eq!(eval("(1 + 2) * 3", 0.0), 9.0);
+        assert_eq!(eval("8 / 4 / 2", 0.0), 1.0);
+        assert_eq!(eval("10 - 4 - 3", 0.0), 3.0);
+        assert_eq!(eval("2 * 3 ^ 2", 0.0), 18.0);
+        //power goes from the right
+        assert_eq!(eval("2 ^ 3 ^ 2", 0.0), 512.0);
+    }
+
+    #[test]
+    fn unary_minus() {
+        assert_eq!(eval("-3", 0.0), -3.0);
+        assert_eq!(eval("--3", 0.0), 3.0);
+        assert_eq!(eval("-2 ^ 2", 0.0), -4.0);
+        assert_eq!(eval("2 ^ -1", 0.0), 0.5);
+        assert_eq!(eval("4 * -t", 2.0), -8.0);
+    }
+
+    #[test]
+    fn names_and_functions() {
+        assert_eq!(eval("t", 1.25), 1.25);
+        assert_close(eval("pi", 0.0), std::f64::consts::PI, 1e-15);
+        assert_close(eval("e", 0.0), std::f64::consts::E, 1e-15);
+        assert_close(eval("0.5 * sin(2 * pi * t)", 0.25), 0.5, 1e-12);
+        assert_close(eval("cos(0) + tan(0) + exp(0) + ln(1)", 0.0), 2.0, 1e-15);
+        assert_eq!(eval("sqrt(abs(-16))", 0.0), 4.0);
+    }
+
+    #[test]
+    fn errors() {
+        for source in [
+            "", "1 +", "(1 + 2", "sin t", "sin(1", "foo(1)", "1 2", "3 $ 4", "1..2",
+        ] {
+            assert!(
+                Expression::parse(source).is_err(),
+                "'{source}' should not parse"
+            );
+        }
+    }
+
+    #[test]
+    fn pivot_derivatives_match_analytic() {
+        let pivot = Pivot::new(PivotMotion::Expression {
+            x: Expression::parse("sin(t)").unwrap(),
+            y: Expression::parse("0").unwrap(),
+        });
+
+        for time in [0.0, 0.7, 2.0, 5.3] {
+            let kinematics = pivot.kinematics(time);
+            assert_close(kinematics.position.0, time.sin(), 1e-12);
+            assert_close(kinematics.velocity.0, time.cos(), 1e-7);
+            assert_close(kinematics.acceleration.0, -time.sin(), 1e-6);
+        }
+    }
+}
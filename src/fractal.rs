@@ -0,0 +1,251 @@
+use std::{
+    f64::consts::PI,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+};
+
+use macroquad::prelude::*;
+use rayon::prelude::*;
+
+use crate::{
+    dulum::Dulum,
+    gravity::GravityField,
+    integrator::IntegratorKind,
+    simulation::{Simulation, DULUMS_COLORS},
+};
+
+///Double pendulum every pixel of the map is simulated with
+#[derive(Clone, Debug)]
+pub struct FlipSettings {
+    pub lengths: [f64; 2],
+    pub masses: [f64; 2],
+    pub gravity: f64,
+    pub time_step: f32,
+    pub integrator: IntegratorKind,
+    ///Pixels which don't flip until then count as never flipping
+    pub max_time: f64,
+}
+
+impl Default for FlipSettings {
+    fn default() -> Self {
+        Self {
+            lengths: [1.0, 1.0],
+            masses: [1.0, 1.0],
+            gravity: 9.8,
+            time_step: 0.01,
+            integrator: IntegratorKind::RungeKutta4,
+            max_time: 10.0,
+        }
+    }
+}
+
+impl FlipSettings {
+    pub fn dulums(&self, angles: (f64, f64)) -> Vec<Dulum> {
+        [angles.0, angles.1]
+            .into_iter()
+            .enumerate()
+            .map(|(i, angle)| {
+                let len = self.lengths[i];
+                Dulum::new(
+                    angle,
+                    len,
+                    self.masses[i],
+                    false,
+                    100.0,
+                    len,
+                    DULUMS_COLORS[i],
+                    0.2,
+                )
+            })
+            .collect()
+    }
+
+    //potential energy of the chain hanging from the pivot with the given angles
+    fn potential(&self, angles: (f64, f64)) -> f64 {
+        let y1 = self.lengths[0] * angles.0.cos();
+        let y2 = y1 + self.lengths[1] * angles.1.cos();
+        -self.gravity * (self.masses[0] * y1 + self.masses[1] * y2)
+    }
+
+    ///Released from rest, a link can only flip if the chain has at least the energy
+    ///of that link standing up with the other one hanging down
+    pub fn can_flip(&self, angles: (f64, f64)) -> bool {
+        let lowest = self.potential((PI, 0.0)).min(self.potential((0.0, PI)));
+        self.potential(angles) >= lowest
+    }
+
+    ///Time until either link goes over the top, None if it doesn't within max_time
+    pub fn flip_time(&self, angles: (f64, f64)) -> Option<f64> {
+        if !self.can_flip(angles) {
+            return None;
+        }
+
+        let mut simulation = Simulation::new(self.dulums(angles));
+        simulation.gravity = GravityField::Uniform {
+            magnitude: self.gravity,
+            direction: 0.0,
+        };
+        simulation.time_step = self.time_step;
+        simulation.integrator = self.integrator.build();
+
+        while simulation.time < self.max_time {
+            //pixels the integrator can't handle count as never flipping
+            simulation.substep().ok()?;
+            if simulation.dulums.iter().any(|dulum| dulum.angle.abs() > PI) {
+                return Some(simulation.time);
+            }
+        }
+
+        None
+    }
+}
+
+///Square window into the plane of initial angles, in radians
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Region {
+    pub center: (f64, f64),
+    pub span: f64,
+}
+
+impl Default for Region {
+    fn default() -> Self {
+        Self {
+            center: (0.0, 0.0),
+            span: 2.0 * PI,
+        }
+    }
+}
+
+impl Region {
+    ///Initial angles in the middle of the pixel, first angle goes right, second one up
+    pub fn angles(&self, x: usize, y: usize, size: usize) -> (f64, f64) {
+        let (u, v) = (
+            (x as f64 + 0.5) / size as f64 - 0.5,
+            (y as f64 + 0.5) / size as f64 - 0.5,
+        );
+        (self.center.0 + u * self.span, self.center.1 - v * self.span)
+    }
+}
+
+//pixels not simulated yet, other values are flip times with infinity for no flip
+const PENDING: u32 = u32::MAX;
+
+///Flip time map computed on a background thread, each row in parallel
+pub struct FlipJob {
+    pub settings: FlipSettings,
+    pub region: Region,
+    pub size: usize,
+
+    times: Arc<Vec<AtomicU32>>,
+    done: Arc<AtomicUsize>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl FlipJob {
+    pub fn start(settings: FlipSettings, region: Region, size: usize) -> Self {
+        let times: Arc<Vec<AtomicU32>> =
+            Arc::new((0..size * size).map(|_| AtomicU32::new(PENDING)).collect());
+        let done = Arc::new(AtomicUsize::new(0));
+        let cancel = Arc::new(AtomicBool::new(false));
+
+        {
+            let (settings, times, done, cancel) = (
+                settings.clone(),
+                times.clone(),
+                done.clone(),
+                cancel.clone(),
+            );
+
+            thread::spawn(move || {
+                (0..size).into_par_iter().for_each(|y| {
+                    for x in 0..size {
+                        if cancel.load(Ordering::Relaxed) {
+                            return;
+                        }
+                        let time = settings
+                            .flip_time(region.angles(x, y, size))
+                            .map_or(f32::INFINITY, |time| time as f32);
+                        times[y * size + x].store(time.to_bits(), Ordering::Relaxed);
+                    }
+                    done.fetch_add(1, Ordering::Relaxed);
+                });
+            });
+        }
+
+        Self {
+            settings,
+            region,
+            size,
+            times,
+            done,
+            cancel,
+        }
+    }
+
+    ///Share of finished rows
+    pub fn progress(&self) -> f32 {
+        self.done.load(Ordering::Relaxed) as f32 / self.size as f32
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.done.load(Ordering::Relaxed) >= self.size
+    }
+
+    ///None if the pixel is not simulated yet, Some(None) if it never flipped
+    pub fn time(&self, x: usize, y: usize) -> Option<Option<f64>> {
+        let bits = self.times[y * self.size + x].load(Ordering::Relaxed);
+        if bits == PENDING {
+            return None;
+        }
+        let time = f32::from_bits(bits);
+        Some(time.is_finite().then_some(time as f64))
+    }
+
+    ///Pending pixels are transparent, so an older map can show through
+    pub fn image(&self) -> Image {
+        let mut image = Image::gen_image_color(self.size as u16, self.size as u16, BLANK);
+
+        for y in 0..self.size {
+            for x in 0..self.size {
+                if let Some(time) = self.time(x, y) {
+                    image.set_pixel(x as u32, y as u32, flip_color(time, self.settings.max_time));
+                }
+            }
+        }
+
+        image
+    }
+
+    pub fn save_png(&self, path: &str) -> Result<(), image::ImageError> {
+        let size = self.size as u32;
+        image::save_buffer(
+            path,
+            &self.image().bytes,
+            size,
+            size,
+            image::ColorType::Rgba8,
+        )
+    }
+}
+
+impl Drop for FlipJob {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+///Black for no flip, quick flips are bright, slow ones fade out through the hues
+pub fn flip_color(time: Option<f64>, max_time: f64) -> Color {
+    let Some(time) = time else {
+        return BLACK;
+    };
+
+    //flip times span orders of magnitude, so go by the logarithm
+    let ratio = ((1.0 + time).ln() / (1.0 + max_time).ln()).clamp(0.0, 1.0) as f32;
+    let hue = 0.75 * ratio;
+    let lightness = 0.6 - 0.45 * ratio;
+    macroquad::color::hsl_to_rgb(hue, 1.0, lightness)
+}
@@ -0,0 +1,101 @@
+use serde::{Deserialize, Serialize};
+
+///Gravitational field used both by the dynamics and the energy,
+///directions are angles from straight down, same as the angles of dulums
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum GravityField {
+    Uniform {
+        magnitude: f64,
+        direction: f64,
+    },
+    ///Attracts to a point, strength is G * M of the attracting body
+    PointMass {
+        position: (f64, f64),
+        strength: f64,
+    },
+    ///Uniform field growing by gradient per meter travelled along its direction
+    Gradient {
+        magnitude: f64,
+        direction: f64,
+        gradient: f64,
+    },
+}
+
+impl Default for GravityField {
+    fn default() -> Self {
+        GravityField::Uniform {
+            magnitude: 9.8,
+            direction: 0.0,
+        }
+    }
+}
+
+impl GravityField {
+    pub const PRESETS: [(&'static str, f64); 4] = [
+        ("Earth", 9.81),
+        ("Moon", 1.62),
+        ("Mars", 3.71),
+        ("Zero-g", 0.0),
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GravityField::Uniform { .. } => "Uniform",
+            GravityField::PointMass { .. } => "Point mass",
+            GravityField::Gradient { .. } => "Linear gradient",
+        }
+    }
+
+    ///Acceleration of a free body at (x, y)
+    pub fn acceleration(&self, x: f64, y: f64) -> (f64, f64) {
+        match *self {
+            GravityField::Uniform {
+                magnitude,
+                direction,
+            } => (magnitude * direction.sin(), magnitude * direction.cos()),
+            GravityField::PointMass { position, strength } => {
+                let (dx, dy) = (position.0 - x, position.1 - y);
+                let distance = dx.hypot(dy);
+                let scale = strength / distance.powi(3);
+                (dx * scale, dy * scale)
+            }
+            GravityField::Gradient {
+                magnitude,
+                direction,
+                gradient,
+            } => {
+                let (sin, cos) = direction.sin_cos();
+                let along = x * sin + y * cos;
+                let magnitude = magnitude + gradient * along;
+                (magnitude * sin, magnitude * cos)
+            }
+        }
+    }
+
+    ///Potential energy of a unit mass at (x, y)
+    pub fn potential(&self, x: f64, y: f64) -> f64 {
+        match *self {
+            GravityField::Uniform {
+                magnitude,
+                direction,
+            } => -magnitude * (x * direction.sin() + y * direction.cos()),
+            GravityField::PointMass { position, strength } => {
+                -strength / (position.0 - x).hypot(position.1 - y)
+            }
+            GravityField::Gradient {
+                magnitude,
+                direction,
+                gradient,
+            } => {
+                let along = x * direction.sin() + y * direction.cos();
+                -(magnitude * along + gradient * along * along / 2.0)
+            }
+        }
+    }
+
+    ///Strength of the field at (x, y)
+    pub fn magnitude_at(&self, x: f64, y: f64) -> f64 {
+        let (ax, ay) = self.acceleration(x, y);
+        ax.hypot(ay)
+    }
+}
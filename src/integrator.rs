@@ -0,0 +1,431 @@
+use nalgebra::DVector;
+use serde::{Deserialize, Serialize};
+
+///Generalized state of the dulums, angle (and len if elastic) per dulum plus their derivatives
+#[derive(Clone, Debug)]
+pub struct State {
+    pub time: f64,
+    pub coordinates: DVector<f64>,
+    pub velocities: DVector<f64>,
+}
+
+///Gives accelerations of the generalized coordinates for a state
+pub type Accelerations<'a> = dyn FnMut(&State) -> DVector<f64> + 'a;
+
+///Send + Sync so whole simulations can be run on other threads
+pub trait Integrator: Send + Sync {
+    fn kind(&self) -> IntegratorKind;
+
+    ///Copy with the same settings and statistics
+    fn boxed_clone(&self) -> Box<dyn Integrator>;
+
+    ///Advances the state by at most dt, returns the time it actually advanced
+    fn step(&mut self, state: &mut State, dt: f64, accelerations: &mut Accelerations) -> f64;
+
+    ///Error control of adaptive integrators, fixed step ones have none
+    fn error_control(&mut self) -> Option<&mut ErrorControl> {
+        None
+    }
+}
+
+impl Clone for Box<dyn Integrator> {
+    fn clone(&self) -> Self {
+        self.boxed_clone()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IntegratorKind {
+    Euler,
+    SemiImplicitEuler,
+    Leapfrog,
+    VelocityVerlet,
+    RungeKutta4,
+    Yoshida4,
+    DormandPrince,
+}
+
+impl IntegratorKind {
+    pub const ALL: [IntegratorKind; 7] = [
+        IntegratorKind::Euler,
+        IntegratorKind::SemiImplicitEuler,
+        IntegratorKind::Leapfrog,
+        IntegratorKind::VelocityVerlet,
+        IntegratorKind::RungeKutta4,
+        IntegratorKind::Yoshida4,
+        IntegratorKind::DormandPrince,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            IntegratorKind::Euler => "Explicit Euler",
+            IntegratorKind::SemiImplicitEuler => "Semi-implicit Euler",
+            IntegratorKind::Leapfrog => "Leapfrog",
+            IntegratorKind::VelocityVerlet => "Velocity Verlet",
+            IntegratorKind::RungeKutta4 => "Runge-Kutta 4",
+            IntegratorKind::Yoshida4 => "Yoshida 4",
+            IntegratorKind::DormandPrince => "Dormand-Prince 5(4)",
+        }
+    }
+
+    pub fn build(&self) -> Box<dyn Integrator> {
+        match self {
+            IntegratorKind::Euler => Box::new(Euler),
+            IntegratorKind::SemiImplicitEuler => Box::new(SemiImplicitEuler),
+            IntegratorKind::Leapfrog => Box::new(Leapfrog),
+            IntegratorKind::VelocityVerlet => Box::new(VelocityVerlet),
+            IntegratorKind::RungeKutta4 => Box::new(RungeKutta4),
+            IntegratorKind::Yoshida4 => Box::new(Yoshida4),
+            IntegratorKind::DormandPrince => Box::new(DormandPrince::default()),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Euler;
+
+impl Integrator for Euler {
+    fn kind(&self) -> IntegratorKind {
+        IntegratorKind::Euler
+    }
+
+    fn boxed_clone(&self) -> Box<dyn Integrator> {
+        Box::new(self.clone())
+    }
+
+    fn step(&mut self, state: &mut State, dt: f64, accelerations: &mut Accelerations) -> f64 {
+        let acc = accelerations(state);
+
+        state.coordinates += &state.velocities * dt;
+        state.velocities += acc * dt;
+        state.time += dt;
+
+        dt
+    }
+}
+
+#[derive(Clone)]
+pub struct SemiImplicitEuler;
+
+impl Integrator for SemiImplicitEuler {
+    fn kind(&self) -> IntegratorKind {
+        IntegratorKind::SemiImplicitEuler
+    }
+
+    fn boxed_clone(&self) -> Box<dyn Integrator> {
+        Box::new(self.clone())
+    }
+
+    fn step(&mut self, state: &mut State, dt: f64, accelerations: &mut Accelerations) -> f64 {
+        let acc = accelerations(state);
+
+        //velocities first, then move with the new ones
+        state.velocities += acc * dt;
+        state.coordinates += &state.velocities * dt;
+        state.time += dt;
+
+        dt
+    }
+}
+
+///Kick-drift-kick, the second kick uses the new positions and the half kicked velocities
+#[derive(Clone)]
+pub struct Leapfrog;
+
+impl Integrator for Leapfrog {
+    fn kind(&self) -> IntegratorKind {
+        IntegratorKind::Leapfrog
+    }
+
+    fn boxed_clone(&self) -> Box<dyn Integrator> {
+        Box::new(self.clone())
+    }
+
+    fn step(&mut self, state: &mut State, dt: f64, accelerations: &mut Accelerations) -> f64 {
+        //split in half for reasons
+        let acc = accelerations(state);
+        state.velocities += acc * (dt / 2.0);
+
+        state.coordinates += &state.velocities * dt;
+        state.time += dt;
+
+        //do it once again, but with newer positions (and velocities, oops)
+        let acc = accelerations(state);
+        state.velocities += acc * (dt / 2.0);
+
+        dt
+    }
+}
+
+///Unlike leapfrog, evaluates the new accelerations with fully predicted velocities
+#[derive(Clone)]
+pub struct VelocityVerlet;
+
+impl Integrator for VelocityVerlet {
+    fn kind(&self) -> IntegratorKind {
+        IntegratorKind::VelocityVerlet
+    }
+
+    fn boxed_clone(&self) -> Box<dyn Integrator> {
+        Box::new(self.clone())
+    }
+
+    fn step(&mut self, state: &mut State, dt: f64, accelerations: &mut Accelerations) -> f64 {
+        let acc = accelerations(state);
+        let velocities = state.velocities.clone();
+
+        state.coordinates += &velocities * dt + &acc * (dt * dt / 2.0);
+        state.velocities += &acc * dt;
+        state.time += dt;
+
+        let new_acc = accelerations(state);
+        state.velocities = velocities + (acc + new_acc) * (dt / 2.0);
+
+        dt
+    }
+}
+
+#[derive(Clone)]
+pub struct RungeKutta4;
+
+impl Integrator for RungeKutta4 {
+    fn kind(&self) -> IntegratorKind {
+        IntegratorKind::RungeKutta4
+    }
+
+    fn boxed_clone(&self) -> Box<dyn Integrator> {
+        Box::new(self.clone())
+    }
+
+    fn step(&mut self, state: &mut State, dt: f64, accelerations: &mut Accelerations) -> f64 {
+        let start = state.clone();
+        let (q1, v1) = (start.velocities.clone(), accelerations(&start));
+
+        let mut probe = |k_q: &DVector<f64>, k_v: &DVector<f64>, h: f64| {
+            let probe = State {
+                time: start.time + h,
+                coordinates: &start.coordinates + k_q * h,
+                velocities: &start.velocities + k_v * h,
+            };
+            let acc = accelerations(&probe);
+            (probe.velocities, acc)
+        };
+
+        let (q2, v2) = probe(&q1, &v1, dt / 2.0);
+        let (q3, v3) = probe(&q2, &v2, dt / 2.0);
+        let (q4, v4) = probe(&q3, &v3, dt);
+
+        state.coordinates += (q1 + q2 * 2.0 + q3 * 2.0 + q4) * (dt / 6.0);
+        state.velocities += (v1 + v2 * 2.0 + v3 * 2.0 + v4) * (dt / 6.0);
+        state.time += dt;
+
+        dt
+    }
+}
+
+///Generalized Stormer-Verlet, symmetric even with velocity dependent accelerations,
+///the implicit first half kick is solved by fixed point iteration
+fn symmetric_leapfrog(state: &mut State, dt: f64, accelerations: &mut Accelerations) {
+    let start = state.velocities.clone();
+
+    let mut acc = accelerations(state);
+    for _ in 0..3 {
+        state.velocities = &start + &acc * (dt / 2.0);
+        acc = accelerations(state);
+    }
+    state.velocities = &start + acc * (dt / 2.0);
+
+    state.coordinates += &state.velocities * dt;
+    state.time += dt;
+
+    let acc = accelerations(state);
+    state.velocities += acc * (dt / 2.0);
+}
+
+///Yoshida's triple jump, three symmetric leapfrog steps with weights giving 4th order
+#[derive(Clone)]
+pub struct Yoshida4;
+
+impl Integrator for Yoshida4 {
+    fn kind(&self) -> IntegratorKind {
+        IntegratorKind::Yoshida4
+    }
+
+    fn boxed_clone(&self) -> Box<dyn Integrator> {
+        Box::new(self.clone())
+    }
+
+    fn step(&mut self, state: &mut State, dt: f64, accelerations: &mut Accelerations) -> f64 {
+        let cbrt = 2f64.powf(1.0 / 3.0);
+        let outer = 1.0 / (2.0 - cbrt);
+        let inner = -cbrt / (2.0 - cbrt);
+
+        for weight in [outer, inner, outer] {
+            symmetric_leapfrog(state, dt * weight, accelerations);
+        }
+
+        dt
+    }
+}
+
+///Settings and statistics of an adaptive integrator
+#[derive(Clone, Debug)]
+pub struct ErrorControl {
+    pub absolute_tolerance: f64,
+    pub relative_tolerance: f64,
+    ///Size of the last accepted step
+    pub step_size: f64,
+    ///Number of steps thrown away since the integrator was created
+    pub rejected: usize,
+
+    next_step: f64,
+}
+
+impl Default for ErrorControl {
+    fn default() -> Self {
+        Self {
+            absolute_tolerance: 1e-8,
+            relative_tolerance: 1e-8,
+            step_size: 0.0,
+            rejected: 0,
+            next_step: 1e-3,
+        }
+    }
+}
+
+//Butcher tableau of Dormand-Prince 5(4)
+const DP_C: [f64; 7] = [0.0, 1.0 / 5.0, 3.0 / 10.0, 4.0 / 5.0, 8.0 / 9.0, 1.0, 1.0];
+const DP_A: [&[f64]; 7] = [
+    &[],
+    &[1.0 / 5.0],
+    &[3.0 / 40.0, 9.0 / 40.0],
+    &[44.0 / 45.0, -56.0 / 15.0, 32.0 / 9.0],
+    &[
+        19372.0 / 6561.0,
+        -25360.0 / 2187.0,
+        64448.0 / 6561.0,
+        -212.0 / 729.0,
+    ],
+    &[
+        9017.0 / 3168.0,
+        -355.0 / 33.0,
+        46732.0 / 5247.0,
+        49.0 / 176.0,
+        -5103.0 / 18656.0,
+    ],
+    //the last stage is evaluated at the 5th order solution
+    &[
+        35.0 / 384.0,
+        0.0,
+        500.0 / 1113.0,
+        125.0 / 192.0,
+        -2187.0 / 6784.0,
+        11.0 / 84.0,
+    ],
+];
+//difference between the 5th and the 4th order weights
+const DP_E: [f64; 7] = [
+    71.0 / 57600.0,
+    0.0,
+    -71.0 / 16695.0,
+    71.0 / 1920.0,
+    -17253.0 / 339200.0,
+    22.0 / 525.0,
+    -1.0 / 40.0,
+];
+///Steps this small are taken even if they miss the tolerance, so the frame can finish
+pub const DP_MIN_STEP: f64 = 1e-7;
+
+///Embedded Runge-Kutta 5(4) which picks its own step size to keep the local error in tolerance
+#[derive(Clone, Default)]
+pub struct DormandPrince {
+    pub control: ErrorControl,
+}
+
+impl Integrator for DormandPrince {
+    fn kind(&self) -> IntegratorKind {
+        IntegratorKind::DormandPrince
+    }
+
+    fn boxed_clone(&self) -> Box<dyn Integrator> {
+        Box::new(self.clone())
+    }
+
+    fn step(&mut self, state: &mut State, dt: f64, accelerations: &mut Accelerations) -> f64 {
+        let control = &mut self.control;
+        let mut stages_q: Vec<DVector<f64>> = Vec::with_capacity(7);
+        let mut stages_v: Vec<DVector<f64>> = Vec::with_capacity(7);
+
+        loop {
+            let clamped = control.next_step >= dt;
+            let h = if clamped { dt } else { control.next_step };
+
+            stages_q.clear();
+            stages_v.clear();
+            let mut probe = state.clone();
+            for (c, a) in DP_C.iter().zip(DP_A) {
+                probe.clone_from(state);
+                probe.time += c * h;
+                for (j, a) in a.iter().enumerate() {
+                    probe.coordinates += &stages_q[j] * (a * h);
+                    probe.velocities += &stages_v[j] * (a * h);
+                }
+
+                stages_v.push(accelerations(&probe));
+                stages_q.push(probe.velocities.clone());
+            }
+
+            //scaled root mean square of the error estimate
+            let mut error_q = DVector::zeros(state.coordinates.len());
+            let mut error_v = DVector::zeros(state.velocities.len());
+            for (j, e) in DP_E.iter().enumerate() {
+                error_q += &stages_q[j] * (e * h);
+                error_v += &stages_v[j] * (e * h);
+            }
+            let scaled = |error: &DVector<f64>, old: &DVector<f64>, new: &DVector<f64>| {
+                error
+                    .iter()
+                    .zip(old.iter().zip(new.iter()))
+                    .map(|(e, (o, n))| {
+                        let scale = control.absolute_tolerance
+                            + control.relative_tolerance * o.abs().max(n.abs());
+                        (e / scale).powi(2)
+                    })
+                    .sum::<f64>()
+            };
+            let count = (error_q.len() + error_v.len()).max(1) as f64;
+            let error = ((scaled(&error_q, &state.coordinates, &probe.coordinates)
+                + scaled(&error_v, &state.velocities, &probe.velocities))
+                / count)
+                .sqrt();
+
+            let factor = if error == 0.0 {
+                5.0
+            } else {
+                (0.9 * error.powf(-1.0 / 5.0)).clamp(0.2, 5.0)
+            };
+            let proposal = (h * factor).max(DP_MIN_STEP);
+
+            if error <= 1.0 || h <= DP_MIN_STEP {
+                //do not let a step shortened by the budget shrink the next one
+                control.next_step = if clamped {
+                    control.next_step.max(proposal)
+                } else {
+                    proposal
+                };
+                control.step_size = h;
+
+                probe.time = state.time + h;
+                *state = probe;
+                return h;
+            }
+
+            control.rejected += 1;
+            control.next_step = if error.is_finite() { proposal } else { h * 0.2 };
+        }
+    }
+
+    fn error_control(&mut self) -> Option<&mut ErrorControl> {
+        Some(&mut self.control)
+    }
+}
@@ -0,0 +1,21 @@
+pub mod collision;
+pub mod constraint;
+pub mod dulum;
+pub mod ensemble;
+pub mod expression;
+pub mod fractal;
+pub mod gravity;
+pub mod integrator;
+pub mod lyapunov;
+pub mod meth;
+pub mod mouse;
+pub mod palette;
+pub mod phase;
+pub mod pivot;
+pub mod presets;
+pub mod scene;
+pub mod section;
+pub mod simulation;
+pub mod solver;
+pub mod spring;
+pub mod sweep;
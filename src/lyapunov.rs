@@ -0,0 +1,138 @@
+use nalgebra::DVector;
+
+use crate::{
+    integrator::State,
+    simulation::{Simulation, SimulationError},
+};
+
+///Estimates the largest Lyapunov exponent from a reference chain and a perturbed copy,
+///their separation is scaled back to the initial one every renormalization interval
+pub struct Lyapunov {
+    pub reference: Simulation,
+    pub perturbed: Simulation,
+    ///Separation of the two chains in generalized coordinates and velocities
+    pub separation: f64,
+    ///Time between renormalizations
+    pub interval: f64,
+    ///(time, estimate) after every renormalization
+    pub history: Vec<[f64; 2]>,
+
+    log_sum: f64,
+    since: f64,
+    //time of the reference when the estimate started
+    start: f64,
+}
+
+impl Lyapunov {
+    ///Copies the simulation twice and pushes the second copy by separation
+    ///equally along every coordinate and velocity, the time stays so a driven pivot keeps its phase
+    pub fn new(simulation: &Simulation, separation: f64, interval: f64) -> Self {
+        let mut reference = simulation.clone();
+        reference.time_budget = 0.0;
+
+        let mut lyapunov = Self {
+            perturbed: reference.clone(),
+            reference,
+            separation,
+            interval,
+            history: Vec::new(),
+            log_sum: 0.0,
+            since: 0.0,
+            start: 0.0,
+        };
+        lyapunov.reset();
+        lyapunov
+    }
+
+    ///Starts the estimate over from the current reference
+    pub fn reset(&mut self) {
+        self.perturbed = self.reference.clone();
+        let state = self.reference.state();
+        let count = state.coordinates.len() + state.velocities.len();
+        let push = self.separation / (count as f64).sqrt();
+        self.perturbed.set_state(&State {
+            time: state.time,
+            coordinates: state.coordinates.add_scalar(push),
+            velocities: state.velocities.add_scalar(push),
+        });
+
+        self.history.clear();
+        self.log_sum = 0.0;
+        self.since = 0.0;
+        self.start = self.reference.time;
+    }
+
+    ///Time the estimate has been running
+    pub fn elapsed(&self) -> f64 {
+        self.reference.time - self.start
+    }
+
+    ///Current difference of the perturbed state from the reference one
+    fn difference(&self) -> (DVector<f64>, DVector<f64>) {
+        let (reference, perturbed) = (self.reference.state(), self.perturbed.state());
+
+        (
+            perturbed.coordinates - reference.coordinates,
+            perturbed.velocities - reference.velocities,
+        )
+    }
+
+    pub fn distance(&self) -> f64 {
+        let (coordinates, velocities) = self.difference();
+        (coordinates.norm_squared() + velocities.norm_squared()).sqrt()
+    }
+
+    ///Advances both chains by dt in steps of the reference time step,
+    ///if the copy fails after the reference moved on the estimate starts over
+    pub fn advance(&mut self, dt: f64) -> Result<(), SimulationError> {
+        let time_step = self.reference.time_step as f64;
+        let mut remaining = dt;
+
+        while remaining > 0.0 {
+            let step = time_step.min(remaining);
+            remaining -= step;
+
+            //the mouse pivot of the copy follows the same target
+            self.perturbed.pivot.target = self.reference.pivot.target;
+            self.reference.advance(step)?;
+            if let Err(err) = self.perturbed.advance(step) {
+                self.reset();
+                return Err(err);
+            }
+
+            self.since += step;
+            if self.since >= self.interval {
+                self.since = 0.0;
+                self.renormalize();
+            }
+        }
+
+        Ok(())
+    }
+
+    fn renormalize(&mut self) {
+        let distance = self.distance();
+        //chains went identical or blew up, nothing to learn from this interval
+        if distance <= 0.0 || !distance.is_finite() {
+            return;
+        }
+
+        self.log_sum += (distance / self.separation).ln();
+        let elapsed = self.elapsed();
+        self.history.push([elapsed, self.log_sum / elapsed]);
+
+        let scale = self.separation / distance;
+        let (coordinates, velocities) = self.difference();
+        let reference = self.reference.state();
+        self.perturbed.set_state(&State {
+            time: reference.time,
+            coordinates: reference.coordinates + coordinates * scale,
+            velocities: reference.velocities + velocities * scale,
+        });
+    }
+
+    ///Latest estimate in 1/s, None before the first renormalization
+    pub fn estimate(&self) -> Option<f64> {
+        self.history.last().map(|[_, estimate]| *estimate)
+    }
+}
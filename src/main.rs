@@ -1,103 +1,257 @@
 #![windows_subsystem = "windows"] //console ma boi
 
-pub mod dulum;
-pub mod meth;
-pub mod mouse;
-
-use std::collections::VecDeque;
-
-use dulum::Dulum;
-use egui::plot::{PlotPoints, Line};
-use macroquad::{
-    hash,
-    prelude::*,
-    ui::{root_ui, widgets},
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
 };
-use nalgebra::{DMatrix, DVector};
 
-use crate::{
-    meth::{deg2rad, normalize_angle, rad2deg},
+use egui::plot::{Line, PlotPoints};
+use fyr_pendulum_too::{
+    collision::Obstacle,
+    constraint::{Constraint, Endpoint},
+    dulum::{Dulum, Parent},
+    ensemble::Ensemble,
+    expression::Expression,
+    gravity::GravityField,
+    integrator::IntegratorKind,
+    lyapunov::Lyapunov,
+    meth,
     mouse::MouseMovement,
+    palette::Palette,
+    phase::{Coordinate, PhaseTrail, Quantity},
+    pivot::PivotMotion,
+    presets::{PendulumWave, Preset},
+    scene::Scene,
+    section::{Direction, Section},
+    simulation::{Simulation, DULUMS_COLORS},
+    solver::SolverKind,
+    spring::Spring,
+    sweep::{self, Parameter, Sampling, Sweep, SweepError, SweepResults},
 };
+use macroquad::{prelude::*, ui::root_ui};
 
-const DULUMS_COLORS: [Color; 6] = [
-    color_u8!(255, 0, 0, 255),
-    color_u8!(255, 255, 0, 255),
-    color_u8!(0, 255, 0, 255),
-    color_u8!(0, 255, 255, 255),
-    color_u8!(0, 0, 255, 255),
-    color_u8!(255, 0, 255, 255),
-];
-
-fn accumulate_jacobi(dulums: &[Dulum]) -> DMatrix<f64> {
-    let count = dulums.len();
-
-    let columns = dulums
-        .iter()
-        .enumerate()
-        .flat_map(|(id, x)| {
-            let (j_vec_1, j_vec_2) = x.get_jacobi_vectors(id, count);
-            [Some(j_vec_1), j_vec_2]
-        })
-        .filter_map(|x| x)
-        .collect::<Vec<_>>();
-
-    DMatrix::from_columns(columns.as_slice())
+///Picks a dulum and its quantity, returns true if anything changed
+fn coordinate_picker(
+    ui: &mut egui::Ui,
+    label: &str,
+    coordinate: &mut Coordinate,
+    count: usize,
+) -> bool {
+    let old = *coordinate;
+
+    ui.horizontal(|ui| {
+        ui.label(label);
+        let mut number = coordinate.dulum + 1;
+        ui.add(
+            egui::DragValue::new(&mut number)
+                .prefix("#")
+                .clamp_range(1..=count.max(1)),
+        );
+        coordinate.dulum = number - 1;
+
+        egui::ComboBox::from_id_source(label)
+            .selected_text(coordinate.quantity.name())
+            .show_ui(ui, |ui| {
+                for quantity in Quantity::ALL {
+                    ui.selectable_value(&mut coordinate.quantity, quantity, quantity.name());
+                }
+            });
+    });
+
+    old != *coordinate
+}
+
+//most dulums the chain can have, the dense solver gets slow long before that
+const MAX_DULUMS: usize = 500;
+
+//sweep running in the background and how many of its values are done
+type SweepJob = (
+    Arc<AtomicUsize>,
+    JoinHandle<Result<SweepResults, SweepError>>,
+);
+
+fn color_button(ui: &mut egui::Ui, color: &mut Color) -> egui::Response {
+    let mut rgba = [color.r, color.g, color.b, color.a];
+    let response = ui.color_edit_button_rgba_unmultiplied(&mut rgba);
+    *color = Color::new(rgba[0], rgba[1], rgba[2], rgba[3]);
+    response
 }
 
-fn accumulate_constraint(dulums: &[Dulum]) -> DMatrix<f64> {
-    let count = dulums.len();
-
-    let columns = dulums
-        .iter()
-        .enumerate()
-        .flat_map(|(id, x)| {
-            let (j_vec_1, j_vec_2) = x.get_partial_constraint(id, count);
-            [Some(j_vec_1), j_vec_2]
-        })
-        .filter_map(|x| x)
-        .collect::<Vec<_>>();
-
-    let partial_constraint = DMatrix::from_columns(columns.as_slice());
-
-    let elements: Vec<_> = dulums
-        .iter()
-        .flat_map(|x| {
-            let (a, b) = x.get_coordinates_der();
-            [Some(a), b]
-        })
-        .filter_map(|x| x)
-        .collect();
-
-    let derivatives = DMatrix::from_vec(elements.len(), 1, elements);
-
-    partial_constraint * derivatives
+//columns of the tables all have the same width so the header and the scrolled rows line up
+const COLUMN_WIDTH: f32 = 80.0;
+
+fn table_grid(id: &str) -> egui::Grid {
+    egui::Grid::new(id)
+        .striped(true)
+        .min_col_width(COLUMN_WIDTH)
+        .max_col_width(COLUMN_WIDTH)
 }
 
-pub fn accumulate_mass(dulums: &[Dulum]) -> DMatrix<f64> {
-    DMatrix::from_diagonal(&DVector::from_iterator(
-        dulums.len() * 2,
-        dulums.iter().flat_map(|x| [x.mass, x.mass]),
-    ))
+///Height of a grid row without the spacing, show_rows adds the item spacing itself
+fn table_row_height(ui: &egui::Ui) -> f32 {
+    ui.spacing().interact_size.y
 }
 
-pub fn accumulate_hooks_force(dulums: &[Dulum]) -> DMatrix<f64> {
-    let elements: Vec<_> = dulums
-        .iter()
-        .flat_map(|x| {
-            let (a, b) = x.hooks_force();
-            [Some(a), b]
-        })
-        .filter_map(|x| x)
-        .collect();
-
-    DMatrix::from_vec(elements.len(), 1, elements)
+///Header of a table, drawn above its ScrollArea so it stays in place
+fn table_header(ui: &mut egui::Ui, id: &str, titles: &[&str]) {
+    table_grid(id).show(ui, |ui| {
+        for title in titles {
+            ui.strong(*title);
+        }
+        ui.end_row();
+    });
 }
 
-pub fn accumulate_gravity(dulums: &[Dulum]) -> DMatrix<f64> {
-    let elements: Vec<_> = dulums.iter().flat_map(|_| [0.0, 9.8]).collect();
+///One row per dulum, only the visible rows get drawn so long chains stay fast,
+///returns true if anything changed
+fn dulum_table(ui: &mut egui::Ui, dulums: &mut [Dulum]) -> bool {
+    const HEADER: [&str; 19] = [
+        "#",
+        "Parent",
+        "Anchor x",
+        "Anchor y",
+        "Color",
+        "Angle",
+        "Angle der",
+        "Len",
+        "Len der",
+        "Mass",
+        "Elastic",
+        "Push",
+        "Hardness",
+        "Default len",
+        "Friction",
+        "Lin drag",
+        "Quad drag",
+        "Spring damp",
+        "Show",
+    ];
+    let positive = 0.001..=f64::INFINITY;
+    let non_negative = 0.0..=f64::INFINITY;
+    let mut changed = false;
 
-    DMatrix::from_vec(elements.len(), 1, elements)
+    let row_height = table_row_height(ui);
+    egui::ScrollArea::horizontal()
+        .id_source("Dulum table")
+        .show(ui, |ui| {
+            table_header(ui, "Dulum table header", &HEADER);
+            egui::ScrollArea::vertical()
+                .max_height(300.0)
+                .id_source("Dulum rows")
+                .show_rows(ui, row_height, dulums.len(), |ui, rows| {
+                    table_grid("Dulum table").show(ui, |ui| {
+                        for ind in rows {
+                            let dulum = &mut dulums[ind];
+                            ui.label(format!("{}", ind + 1));
+                            //numbered like the dulums, 0 is the pivot
+                            let mut parent = dulum.parent.index(ind).map_or(0, |parent| parent + 1);
+                            if ui
+                                .add(egui::DragValue::new(&mut parent).clamp_range(0..=ind))
+                                .on_hover_text("0 is the pivot")
+                                .changed()
+                            {
+                                dulum.parent = match parent {
+                                    0 => Parent::Pivot,
+                                    parent => Parent::Dulum(parent - 1),
+                                };
+                                changed = true;
+                            }
+                            //only dulums on the pivot have an anchor
+                            let root = dulum.parent.index(ind).is_none();
+                            changed |= ui
+                                .add_enabled(
+                                    root,
+                                    egui::DragValue::new(&mut dulum.anchor.0).speed(0.01),
+                                )
+                                .changed();
+                            changed |= ui
+                                .add_enabled(
+                                    root,
+                                    egui::DragValue::new(&mut dulum.anchor.1).speed(0.01),
+                                )
+                                .changed();
+                            changed |= color_button(ui, &mut dulum.color).changed();
+                            changed |= meth::drag_angle(ui, &mut dulum.angle).changed();
+                            changed |= meth::drag_angle(ui, &mut dulum.angle_der).changed();
+                            changed |= ui
+                                .add(
+                                    egui::DragValue::new(&mut dulum.len)
+                                        .speed(0.01)
+                                        .clamp_range(positive.clone()),
+                                )
+                                .changed();
+                            changed |= ui
+                                .add(egui::DragValue::new(&mut dulum.len_der).speed(0.01))
+                                .changed();
+                            changed |= ui
+                                .add(
+                                    egui::DragValue::new(&mut dulum.mass)
+                                        .speed(0.01)
+                                        .clamp_range(positive.clone()),
+                                )
+                                .changed();
+                            changed |= ui.checkbox(&mut dulum.elastic, "").changed();
+                            changed |= ui.checkbox(&mut dulum.push_elastic, "").changed();
+                            changed |= ui
+                                .add(
+                                    egui::DragValue::new(&mut dulum.hardness)
+                                        .clamp_range(non_negative.clone()),
+                                )
+                                .changed();
+                            changed |= ui
+                                .add(
+                                    egui::DragValue::new(&mut dulum.default_len)
+                                        .speed(0.01)
+                                        .clamp_range(positive.clone()),
+                                )
+                                .changed();
+                            changed |= ui
+                                .add(
+                                    egui::DragValue::new(&mut dulum.joint_friction)
+                                        .speed(0.01)
+                                        .clamp_range(non_negative.clone()),
+                                )
+                                .changed();
+                            changed |= ui
+                                .add(
+                                    egui::DragValue::new(&mut dulum.linear_drag)
+                                        .speed(0.01)
+                                        .clamp_range(non_negative.clone()),
+                                )
+                                .changed();
+                            changed |= ui
+                                .add(
+                                    egui::DragValue::new(&mut dulum.quadratic_drag)
+                                        .speed(0.01)
+                                        .clamp_range(non_negative.clone()),
+                                )
+                                .changed();
+                            changed |= ui
+                                .add(
+                                    egui::DragValue::new(&mut dulum.spring_damping)
+                                        .speed(0.01)
+                                        .clamp_range(non_negative.clone()),
+                                )
+                                .changed();
+                            ui.horizontal(|ui| {
+                                ui.checkbox(&mut dulum.visible, "")
+                                    .on_hover_text("Show dulum");
+                                ui.checkbox(&mut dulum.visible_line, "")
+                                    .on_hover_text("Show line");
+                                ui.checkbox(&mut dulum.visible_trace, "")
+                                    .on_hover_text("Show trace");
+                            });
+                            ui.end_row();
+                        }
+                    });
+                });
+        });
+
+    changed
 }
 
 #[macroquad::main("Multiple Pendulums")]
@@ -108,17 +262,118 @@ async fn main() {
 
     let mut mouse = MouseMovement::new();
     //prepare state
-    let mut dulums = vec![
+    let mut simulation = Simulation::new(vec![
         Dulum::new(0.0, 2.0, 1.0, false, 100.0, 2.0, DULUMS_COLORS[0], 0.2),
         Dulum::new(0.0, 2.0, 1.0, false, 100.0, 2.0, DULUMS_COLORS[1], 0.2),
-    ];
+    ]);
+
+    //colors of the chain, applied whenever it changes
+    let mut palette = Palette::default();
+    //length and mass every dulum gets from "Set all"
+    let mut all_len = 2.0;
+    let mut all_mass = 1.0;
+    //row of pendulums the generator makes
+    let mut wave = PendulumWave::default();
 
-    let mut time_step: f32 = 0.001;
     //za warudo
     let mut simulate: bool = false;
-    let mut time_budget: f32 = 0.0;
 
-    //energy trails 
+    //scene files, the first argument is opened at startup
+    let mut scene_path = String::from("scene.ron");
+    let mut scene_message = String::new();
+    if let Some(path) = std::env::args().nth(1) {
+        match Scene::load(&path) {
+            Ok(scene) => {
+                simulation = scene.to_simulation();
+                palette = scene.palette;
+                camera_scale = scene.camera_scale;
+                camera_origin = scene.camera_origin();
+            }
+            Err(err) => scene_message = format!("Could not load {path}: {err}"),
+        }
+        scene_path = path;
+    }
+
+    //dragging bobs with the mouse
+    let mut dragged: Option<usize> = None;
+    let mut throw = true;
+    let mut egui_wants_pointer = false;
+
+    //text of the pivot expressions, applied once they parse
+    let mut pivot_expressions = (
+        String::from("0"),
+        String::from("0.2 * sin(2 * pi * 20 * t)"),
+    );
+    let mut pivot_error = String::new();
+
+    //phase space plot
+    let mut show_phase = false;
+    let mut phase_trail = PhaseTrail::new(
+        Coordinate::new(0, Quantity::Angle),
+        Coordinate::new(0, Quantity::AngleDer),
+    );
+
+    //poincaré section
+    let mut show_section = false;
+    let mut section = Section::new(
+        Coordinate::new(0, Quantity::Angle),
+        Coordinate::new(1, Quantity::Angle),
+        Coordinate::new(1, Quantity::AngleDer),
+    );
+    let mut section_path = String::from("section.csv");
+    let mut section_message = String::new();
+
+    //largest lyapunov exponent, runs its own copies of the chain
+    let mut show_lyapunov = false;
+    let mut lyapunov: Option<Lyapunov> = None;
+    let mut lyapunov_running = true;
+    let mut lyapunov_separation = 1e-8;
+    let mut lyapunov_interval = 0.1;
+    let mut lyapunov_speed = 1.0f32;
+
+    //butterfly effect, copies of the chain with a slightly turned dulum
+    let mut show_ensemble = false;
+    let mut ensemble: Option<Ensemble> = None;
+    let mut ensemble_count = 4;
+    let mut ensemble_dulum = 1;
+    let mut ensemble_perturbation = 1e-4;
+
+    //bifurcation diagram, computed on a background thread
+    let mut show_sweep = false;
+    let mut sweep = Sweep {
+        base: simulation.clone(),
+        parameter: Parameter::DrivingAmplitude,
+        from: 0.0,
+        to: 1.0,
+        count: 100,
+        transient: 20.0,
+        duration: 50.0,
+        coordinate: Coordinate::new(0, Quantity::Angle),
+        sampling: Sampling::Stroboscopic { period: 1.0 },
+    };
+    let mut sweep_strobe = true;
+    let mut sweep_period = 1.0;
+    let mut sweep_job: Option<SweepJob> = None;
+    let mut sweep_results: SweepResults = Vec::new();
+    let mut sweep_path = String::from("bifurcation.csv");
+    let mut sweep_message = String::new();
+
+    //constraint editing and how much they are violated
+    let mut rod_a = 1;
+    let mut rod_b = 2;
+    let mut rod_point = (0.0, 0.0);
+    let mut violation_trail: VecDeque<f64> = VecDeque::with_capacity(1024);
+
+    //new springs between bobs
+    let mut spring_a = 1;
+    let mut spring_b = 2;
+    let mut spring_point = (0.0, 0.0);
+    let mut spring_hardness = 100.0;
+
+    //why the simulation paused itself
+    let mut simulation_error = String::new();
+
+    //energy trails
     let mut energy_trail: VecDeque<f64> = VecDeque::with_capacity(1024);
 
     loop {
@@ -143,77 +398,7 @@ async fn main() {
         if is_key_down(KeyCode::Down) {
             camera_scale /= 3.00f32.powf(get_frame_time());
         }
-        //step the dulums
-        if simulate {
-            //add to the time budget
-            time_budget += get_frame_time();
-            //snow balling protection 
-            if time_budget >= 1.0 {
-                time_budget = 0.0;
-                simulate = false;
-            }
-
-            while time_budget >= time_step {
-                time_budget -= time_step;
-                //gain variables
-                let jacobi = accumulate_jacobi(&dulums);
-                let jacobi_trans = jacobi.transpose();
-                let mass = accumulate_mass(&dulums);
-                let constraint = accumulate_constraint(&dulums);
-                let hooks = accumulate_hooks_force(&dulums);
-                let gravity = accumulate_gravity(&dulums);
-
-                //calculate sides
-                let left = jacobi_trans.clone() * mass.clone() * jacobi;
-                let Some(left) = left.try_inverse() else {
-                    panic!("If no inverse, no working!");
-                };
-                let right = hooks + jacobi_trans * mass.clone() * (gravity.clone() - constraint);
-
-                let shit = left * right;
-                //extract values
-                let mut pointer = 0;
-                for dulum in &mut dulums {
-                    if dulum.is_elastic() {
-                        dulum.leapfrog_part_one(time_step as f64, shit[pointer], shit[pointer + 1]);
-                        pointer += 2;
-                    } else {
-                        dulum.leapfrog_part_one(time_step as f64, shit[pointer], 0.0);
-                        pointer += 1;
-                    }
-                }
-                //do it once again, but with newer positions (and velocities, oops)
-                //gain variables
-                let jacobi = accumulate_jacobi(&dulums);
-                let jacobi_trans = jacobi.transpose();
-                let constraint = accumulate_constraint(&dulums);
-                let hooks = accumulate_hooks_force(&dulums);
-
-                //calculate sides
-                let left = jacobi_trans.clone() * mass.clone() * jacobi;
-                let Some(left) = left.try_inverse() else {
-                    panic!("If no inverse, no working!");
-                };
-                let right = hooks + jacobi_trans * mass * (gravity - constraint);
-
-                let shit = left * right;
-                //extract values
-                let mut pointer = 0;
-                for dulum in &mut dulums {
-                    if dulum.is_elastic() {
-                        dulum.leapfrog_part_two(time_step as f64, shit[pointer], shit[pointer + 1]);
-                        pointer += 2;
-                    } else {
-                        dulum.leapfrog_part_two(time_step as f64, shit[pointer], 0.0);
-                        pointer += 1;
-                    }
-                }
-            }
-        }
-
-        clear_background(BLACK);
-
-        set_camera(&Camera2D {
+        let camera = Camera2D {
             rotation: 0.0,
             zoom: vec2(
                 camera_scale / screen_width(),
@@ -223,113 +408,1059 @@ async fn main() {
             offset: vec2(0.0, 0.0),
             render_target: None,
             viewport: None,
-        });
+        };
+        mouse.update_world(&camera);
+
+        //drag bobs while paused
+        if !simulate && !egui_wants_pointer && is_mouse_button_pressed(MouseButton::Left) {
+            dragged = simulation
+                .positions()
+                .iter()
+                .zip(&simulation.dulums)
+                .enumerate()
+                .map(|(ind, ((x, y), dulum))| {
+                    let distance = (*x as f32 - mouse.world_x).hypot(*y as f32 - mouse.world_y);
+                    (ind, distance - dulum.size)
+                })
+                .filter(|(_, distance)| *distance <= 0.0)
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(ind, _)| ind);
+        }
+        if let Some(ind) = dragged {
+            let ((previous_x, previous_y), _) = simulation.parent_kinematics()[ind];
+            let dulum = &mut simulation.dulums[ind];
+            dulum.aim(
+                previous_x,
+                previous_y,
+                mouse.world_x as f64,
+                mouse.world_y as f64,
+            );
+
+            if !is_mouse_button_down(MouseButton::Left) {
+                dragged = None;
+                //throw it with the speed of the mouse
+                if throw {
+                    let (_, (parent_vx, parent_vy)) = simulation.parent_kinematics()[ind];
+                    let (vx, vy) = mouse.velocity();
+                    let vx = (vx / camera_scale) as f64 - parent_vx;
+                    let vy = (vy / camera_scale) as f64 - parent_vy;
+                    simulation.dulums[ind].set_relative_velocity(vx, vy);
+                    simulate = true;
+                }
+            }
+        }
 
-        //render the dulums
-        //trails
-        let mut previous_x: f32 = 0.0;
-        let mut previous_y: f32 = 0.0;
-        for dulum in &mut dulums {
-            (previous_x, previous_y) = dulum.add_trail(previous_x, previous_y);
-            dulum.render_trail();
+        //mouse driven pivot follows the cursor while left button is held
+        if simulate
+            && !egui_wants_pointer
+            && dragged.is_none()
+            && is_mouse_button_down(MouseButton::Left)
+        {
+            simulation.pivot.target = (mouse.world_x as f64, mouse.world_y as f64);
+        }
+
+        //step the dulums
+        if !simulate {
+            //the state may jump while paused
+            section.interrupt();
+        }
+        if simulate {
+            match simulation
+                .step_observed(get_frame_time(), |simulation| section.observe(simulation))
+            {
+                Ok(running) => {
+                    simulate = running;
+                    simulation_error.clear();
+                }
+                Err(err) => {
+                    simulate = false;
+                    simulation_error = format!("Paused: {err}");
+                }
+            }
+        }
+        if simulate {
+            phase_trail.record(&simulation);
+            match ensemble
+                .as_mut()
+                .map(|ensemble| ensemble.step(get_frame_time(), &simulation))
+            {
+                Some(Ok(false)) => {
+                    simulate = false;
+                    simulation_error = String::from("Paused, butterfly copy fell behind");
+                }
+                Some(Err(err)) => {
+                    simulate = false;
+                    simulation_error = format!("Paused, butterfly copy failed: {err}");
+                }
+                _ => {}
+            }
+        }
+        if sweep_job
+            .as_ref()
+            .is_some_and(|(_, handle)| handle.is_finished())
+        {
+            let (_, handle) = sweep_job.take().unwrap();
+            match handle.join() {
+                Ok(Ok(results)) => sweep_results = results,
+                Ok(Err(err)) => sweep_message = format!("Could not sweep: {err}"),
+                Err(_) => sweep_message = String::from("The sweep crashed"),
+            }
         }
-        (previous_x, previous_y) = (0.0, 0.0);
-        //lines
-        for dulum in &dulums {
-            (previous_x, previous_y) = dulum.render_line(previous_x, previous_y);
+        if let Some(lyapunov) = lyapunov.as_mut().filter(|_| lyapunov_running) {
+            //same snow balling protection as the simulation
+            if let Err(err) = lyapunov.advance((get_frame_time() * lyapunov_speed).min(1.0) as f64)
+            {
+                lyapunov_running = false;
+                simulation_error = format!("Lyapunov estimate stopped: {err}");
+            }
         }
 
-        //mass
-        (previous_x, previous_y) = (0.0, 0.0);
+        clear_background(BLACK);
+
+        set_camera(&camera);
+
+        //attracting body of a point mass field
+        if let GravityField::PointMass { position, .. } = simulation.gravity {
+            draw_circle(position.0 as f32, position.1 as f32, 0.3, GRAY);
+        }
 
-        for dulum in &dulums {
-            (previous_x, previous_y) = dulum.render_circle(previous_x, previous_y);
+        //render the dulums, copies behind the original
+        if let Some(ensemble) = &mut ensemble {
+            ensemble.render();
         }
+        simulation.render();
 
         //egui
         set_default_camera();
 
+        if !simulation_error.is_empty() {
+            draw_text(&simulation_error, 10.0, screen_height() - 20.0, 30.0, RED);
+        }
+
         egui_macroquad::ui(|egui_ctx| {
+            egui_wants_pointer = egui_ctx.wants_pointer_input();
+
+            egui::Window::new("Phase space")
+                .open(&mut show_phase)
+                .show(egui_ctx, |ui| {
+                    let count = simulation.dulums.len();
+                    let mut changed =
+                        coordinate_picker(ui, "Horizontal", &mut phase_trail.x, count);
+                    changed |= coordinate_picker(ui, "Vertical", &mut phase_trail.y, count);
+                    changed |= ui.checkbox(&mut phase_trail.wrap, "Wrap angles").changed();
+                    ui.horizontal(|ui| {
+                        ui.label("History");
+                        ui.add(
+                            egui::Slider::new(&mut phase_trail.history, 16..=20000)
+                                .logarithmic(true),
+                        );
+                    });
+                    if changed || ui.button("Clear").clicked() {
+                        phase_trail.clear();
+                    }
+
+                    egui::plot::Plot::new("Phase space plot")
+                        .view_aspect(1.0)
+                        .x_axis_formatter({
+                            let label = phase_trail.x.label();
+                            move |x, _| format!("{x:.2} {label}")
+                        })
+                        .y_axis_formatter({
+                            let label = phase_trail.y.label();
+                            move |y, _| format!("{y:.2} {label}")
+                        })
+                        .show(ui, |plot| {
+                            for segment in phase_trail.segments() {
+                                plot.line(Line::new(PlotPoints::new(segment)));
+                            }
+                        });
+                });
+
+            egui::Window::new("Poincaré section")
+                .open(&mut show_section)
+                .show(egui_ctx, |ui| {
+                    let count = simulation.dulums.len();
+                    let mut changed = coordinate_picker(ui, "Surface", &mut section.surface, count);
+                    ui.horizontal(|ui| {
+                        ui.label("crosses");
+                        changed |= if section.surface.quantity == Quantity::Angle {
+                            meth::drag_angle(ui, &mut section.level).changed()
+                        } else {
+                            ui.add(egui::DragValue::new(&mut section.level).speed(0.01))
+                                .changed()
+                        };
+                        egui::ComboBox::from_id_source("Section direction")
+                            .selected_text(section.direction.name())
+                            .show_ui(ui, |ui| {
+                                for direction in Direction::ALL {
+                                    changed |= ui
+                                        .selectable_value(
+                                            &mut section.direction,
+                                            direction,
+                                            direction.name(),
+                                        )
+                                        .changed();
+                                }
+                            });
+                    });
+                    changed |= coordinate_picker(ui, "Horizontal", &mut section.x, count);
+                    changed |= coordinate_picker(ui, "Vertical", &mut section.y, count);
+
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{} crossings", section.points.len()));
+                        if changed || ui.button("Clear").clicked() {
+                            section.clear();
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.text_edit_singleline(&mut section_path);
+                        if ui.button("Export CSV").clicked() {
+                            section_message = match section.save_csv(&section_path) {
+                                Ok(()) => format!("Exported {section_path}"),
+                                Err(err) => format!("Could not export {section_path}: {err}"),
+                            };
+                        }
+                    });
+                    if !section_message.is_empty() {
+                        ui.label(&section_message);
+                    }
+
+                    egui::plot::Plot::new("Poincaré section plot")
+                        .view_aspect(1.0)
+                        .x_axis_formatter({
+                            let label = section.x.label();
+                            move |x, _| format!("{x:.2} {label}")
+                        })
+                        .y_axis_formatter({
+                            let label = section.y.label();
+                            move |y, _| format!("{y:.2} {label}")
+                        })
+                        .show(ui, |plot| {
+                            plot.points(egui::plot::Points::new(section.plot_points()).radius(1.5));
+                        });
+                });
+
+            egui::Window::new("Lyapunov exponent")
+                .open(&mut show_lyapunov)
+                .show(egui_ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Separation");
+                        ui.add(
+                            egui::Slider::new(&mut lyapunov_separation, 1e-12..=1e-3)
+                                .logarithmic(true),
+                        );
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Renormalize every");
+                        ui.add(
+                            egui::Slider::new(&mut lyapunov_interval, 0.01..=2.0)
+                                .logarithmic(true)
+                                .suffix(" s"),
+                        );
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Speed");
+                        ui.add(
+                            egui::Slider::new(&mut lyapunov_speed, 0.1..=50.0)
+                                .logarithmic(true)
+                                .suffix("x"),
+                        );
+                    });
+                    ui.horizontal(|ui| {
+                        if ui.button("Start from current state").clicked() {
+                            lyapunov = Some(Lyapunov::new(
+                                &simulation,
+                                lyapunov_separation,
+                                lyapunov_interval,
+                            ));
+                            lyapunov_running = true;
+                        }
+                        ui.checkbox(&mut lyapunov_running, "Run");
+                    });
+
+                    let Some(lyapunov) = &lyapunov else {
+                        return;
+                    };
+                    ui.label(format!("Time: {:.1} s", lyapunov.elapsed()));
+                    ui.label(format!("Distance: {:.3e}", lyapunov.distance()));
+                    match lyapunov.estimate() {
+                        Some(estimate) => ui.label(format!("Estimate: {estimate:.4} 1/s")),
+                        None => ui.label("Estimate: waiting for the first renormalization"),
+                    };
+
+                    egui::plot::Plot::new("Lyapunov convergence")
+                        .view_aspect(2.0)
+                        .show(ui, |plot| {
+                            plot.line(Line::new(PlotPoints::new(lyapunov.history.clone())));
+                        });
+                });
+
+            egui::Window::new("Butterfly effect")
+                .open(&mut show_ensemble)
+                .show(egui_ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Copies");
+                        ui.add(egui::Slider::new(&mut ensemble_count, 1..=12));
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Turn dulum");
+                        ui.add(
+                            egui::DragValue::new(&mut ensemble_dulum)
+                                .prefix("#")
+                                .clamp_range(1..=simulation.dulums.len().max(1)),
+                        );
+                        ui.label("by");
+                        let mut degrees = meth::rad2deg(ensemble_perturbation);
+                        if ui
+                            .add(
+                                egui::DragValue::new(&mut degrees)
+                                    .speed(0.0001)
+                                    .max_decimals(8)
+                                    .suffix("°"),
+                            )
+                            .changed()
+                        {
+                            ensemble_perturbation = meth::deg2rad(degrees);
+                        }
+                        ui.label("per copy");
+                    });
+                    ui.horizontal(|ui| {
+                        if ui.button("Spawn copies").clicked() {
+                            ensemble = Some(Ensemble::new(
+                                &simulation,
+                                ensemble_count,
+                                ensemble_dulum - 1,
+                                ensemble_perturbation,
+                            ));
+                        }
+                        if ui.button("Remove copies").clicked() {
+                            ensemble = None;
+                        }
+                    });
+
+                    let Some(ensemble) = &ensemble else {
+                        return;
+                    };
+                    ui.label("Distance from the original in generalized coordinates");
+                    egui::plot::Plot::new("Divergence")
+                        .view_aspect(2.0)
+                        .y_axis_formatter(|y, _| format!("1e{y:.0}"))
+                        .show(ui, |plot| {
+                            for (copy, divergence) in ensemble.divergence.iter().enumerate() {
+                                let color = ensemble.color(copy);
+                                plot.line(
+                                    Line::new(PlotPoints::new(
+                                        divergence.iter().copied().collect(),
+                                    ))
+                                    .color(
+                                        egui::Color32::from_rgb(
+                                            (color.r * 255.0) as u8,
+                                            (color.g * 255.0) as u8,
+                                            (color.b * 255.0) as u8,
+                                        ),
+                                    ),
+                                );
+                            }
+                        });
+                });
+
+            egui::Window::new("Bifurcation diagram")
+                .open(&mut show_sweep)
+                .show(egui_ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Sweep");
+                        let dulum = sweep.parameter.dulum().unwrap_or(0);
+                        egui::ComboBox::from_id_source("Sweep parameter")
+                            .selected_text(sweep.parameter.name())
+                            .show_ui(ui, |ui| {
+                                for parameter in Parameter::all(dulum) {
+                                    ui.selectable_value(
+                                        &mut sweep.parameter,
+                                        parameter,
+                                        parameter.name(),
+                                    );
+                                }
+                            });
+                        if let Some(dulum) = sweep.parameter.dulum() {
+                            let mut number = dulum + 1;
+                            ui.add(
+                                egui::DragValue::new(&mut number)
+                                    .prefix("#")
+                                    .clamp_range(1..=simulation.dulums.len().max(1)),
+                            );
+                            sweep.parameter = sweep.parameter.with_dulum(number - 1);
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("from");
+                        ui.add(egui::DragValue::new(&mut sweep.from).speed(0.01));
+                        ui.label("to");
+                        ui.add(egui::DragValue::new(&mut sweep.to).speed(0.01));
+                        ui.label("in");
+                        ui.add(egui::DragValue::new(&mut sweep.count).clamp_range(2..=2000));
+                        ui.label("values");
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Transient");
+                        ui.add(
+                            egui::DragValue::new(&mut sweep.transient)
+                                .speed(0.1)
+                                .clamp_range(0.0..=f64::INFINITY)
+                                .suffix(" s"),
+                        );
+                        ui.label("then sample for");
+                        ui.add(
+                            egui::DragValue::new(&mut sweep.duration)
+                                .speed(0.1)
+                                .clamp_range(0.0..=f64::INFINITY)
+                                .suffix(" s"),
+                        );
+                    });
+                    let count = simulation.dulums.len();
+                    coordinate_picker(ui, "Sample", &mut sweep.coordinate, count);
+                    ui.radio_value(
+                        &mut sweep_strobe,
+                        false,
+                        "At the crossings of the Poincaré section surface",
+                    );
+                    ui.horizontal(|ui| {
+                        ui.radio_value(&mut sweep_strobe, true, "Every");
+                        ui.add(
+                            egui::DragValue::new(&mut sweep_period)
+                                .speed(0.01)
+                                .clamp_range(0.001..=f64::INFINITY)
+                                .suffix(" s"),
+                        );
+                        if let PivotMotion::Vertical { frequency, .. }
+                        | PivotMotion::Horizontal { frequency, .. } = simulation.pivot.motion
+                        {
+                            if frequency > 0.0 && ui.button("Driving period").clicked() {
+                                sweep_period = 1.0 / frequency;
+                            }
+                        }
+                    });
+
+                    ui.horizontal(|ui| {
+                        match &sweep_job {
+                            Some((done, _)) => {
+                                let progress =
+                                    done.load(Ordering::Relaxed) as f32 / sweep.count as f32;
+                                ui.add(egui::ProgressBar::new(progress).show_percentage());
+                            }
+                            None => {
+                                if ui.button("Run").clicked() {
+                                    let mut job = sweep.clone();
+                                    job.base = simulation.clone();
+                                    job.sampling = if sweep_strobe {
+                                        Sampling::Stroboscopic {
+                                            period: sweep_period,
+                                        }
+                                    } else {
+                                        Sampling::Section(section.clone())
+                                    };
+                                    //refuse to run the same simulation count times
+                                    if let Err(err) = job.check() {
+                                        sweep_message = format!("Could not sweep: {err}");
+                                    } else {
+                                        sweep_message.clear();
+                                        let done = Arc::new(AtomicUsize::new(0));
+                                        let handle = {
+                                            let done = done.clone();
+                                            thread::spawn(move || job.run(&done))
+                                        };
+                                        sweep_job = Some((done, handle));
+                                    }
+                                }
+                            }
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.text_edit_singleline(&mut sweep_path);
+                        if ui.button("Export CSV").clicked() {
+                            sweep_message = match sweep::save_csv(
+                                &sweep_path,
+                                sweep.parameter,
+                                sweep.coordinate,
+                                &sweep_results,
+                            ) {
+                                Ok(()) => format!("Exported {sweep_path}"),
+                                Err(err) => format!("Could not export {sweep_path}: {err}"),
+                            };
+                        }
+                    });
+                    if !sweep_message.is_empty() {
+                        ui.label(&sweep_message);
+                    }
+
+                    egui::plot::Plot::new("Bifurcation plot")
+                        .view_aspect(1.5)
+                        .show(ui, |plot| {
+                            plot.points(
+                                egui::plot::Points::new(sweep::diagram_points(&sweep_results))
+                                    .radius(1.0),
+                            );
+                        });
+                });
+
             egui::Window::new("Simulation controls").show(egui_ctx, |ui| {
+                //ready made setups
+                ui.menu_button("Presets", |ui| {
+                    for preset in Preset::ALL {
+                        if ui.button(preset.name()).clicked() {
+                            simulation.replace_dulums(preset.dulums(simulation.gravity.magnitude_at(0.0, 0.0)));
+                            simulate = false;
+                            energy_trail.clear();
+                            ui.close_menu();
+                        }
+                    }
+                });
                 //simulace?
                 ui.add(egui::Checkbox::new(&mut simulate, "Simulate"));
+                if !simulation_error.is_empty() {
+                    ui.colored_label(egui::Color32::RED, &simulation_error);
+                }
+                ui.checkbox(&mut show_phase, "Show phase space");
+                ui.checkbox(&mut show_section, "Show Poincaré section");
+                ui.checkbox(&mut show_lyapunov, "Show Lyapunov exponent");
+                ui.checkbox(&mut show_ensemble, "Show butterfly effect");
+                ui.checkbox(&mut show_sweep, "Show bifurcation diagram");
+                ui.checkbox(&mut throw, "Throw dragged dulums")
+                    .on_hover_text("Left click and drag a bob while paused to move it");
                 //time step size
                 ui.horizontal(|ui| {
                     ui.label("Step size");
                     ui.add(
-                        egui::Slider::new(&mut time_step, 0.0..=1.0)
+                        egui::Slider::new(&mut simulation.time_step, 1e-5..=1.0)
                             .logarithmic(true)
                     );
                 });
+                //integrator
+                let mut kind = simulation.integrator.kind();
+                egui::ComboBox::from_label("Integrator")
+                    .selected_text(kind.name())
+                    .show_ui(ui, |ui| {
+                        for option in IntegratorKind::ALL {
+                            ui.selectable_value(&mut kind, option, option.name());
+                        }
+                    });
+                if kind != simulation.integrator.kind() {
+                    simulation.integrator = kind.build();
+                }
+                //solver of the equations of motion
+                egui::ComboBox::from_label("Solver")
+                    .selected_text(simulation.solver.name())
+                    .show_ui(ui, |ui| {
+                        for option in SolverKind::ALL {
+                            ui.selectable_value(&mut simulation.solver, option, option.name());
+                        }
+                    })
+                    .response
+                    .on_hover_text("Recursive is linear in the number of dulums, rods and pins always use the dense one");
+                //adaptive stepping
+                if let Some(control) = simulation.integrator.error_control() {
+                    ui.horizontal(|ui| {
+                        ui.label("Absolute tolerance");
+                        ui.add(
+                            egui::Slider::new(&mut control.absolute_tolerance, 1e-12..=1e-2)
+                                .logarithmic(true)
+                        );
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Relative tolerance");
+                        ui.add(
+                            egui::Slider::new(&mut control.relative_tolerance, 1e-12..=1e-2)
+                                .logarithmic(true)
+                        );
+                    });
+                    ui.label(format!("Accepted step: {:.2e}", control.step_size));
+                    ui.label(format!("Rejected steps: {}", control.rejected));
+                }
                 //number of dulums
-                let mut expected_dulums = dulums.len();
+                let mut expected_dulums = simulation.dulums.len();
                 ui.horizontal(|ui| {
                     ui.label("Number of Dulums");
-                    ui.add(egui::Slider::new(&mut expected_dulums, 1..=6));
+                    ui.add(egui::Slider::new(&mut expected_dulums, 1..=MAX_DULUMS).logarithmic(true));
                 });
+                if simulation.dulums.len() > 30 && simulation.solver == SolverKind::Dense {
+                    ui.label("Long chains run much faster with the recursive solver");
+                }
                 if ui.button("Reset").clicked() {
-                    dulums.clear();
+                    simulation.replace_dulums(Vec::new());
+                    energy_trail.clear();
                 }
+
+                //saving and loading
+                egui::CollapsingHeader::new("Scene").show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("File");
+                        ui.text_edit_singleline(&mut scene_path);
+                    });
+                    ui.horizontal(|ui| {
+                        if ui.button("Save").clicked() {
+                            let scene = Scene::capture(&simulation, palette, camera_scale, camera_origin);
+                            scene_message = match scene.save(&scene_path) {
+                                Ok(()) => format!("Saved {scene_path}"),
+                                Err(err) => format!("Could not save {scene_path}: {err}"),
+                            };
+                        }
+                        if ui.button("Load").clicked() {
+                            match Scene::load(&scene_path) {
+                                Ok(scene) => {
+                                    simulation = scene.to_simulation();
+                                    palette = scene.palette;
+                                    camera_scale = scene.camera_scale;
+                                    camera_origin = scene.camera_origin();
+                                    simulate = false;
+                                    energy_trail.clear();
+                                    scene_message = format!("Loaded {scene_path}");
+                                }
+                                Err(err) => {
+                                    scene_message = format!("Could not load {scene_path}: {err}")
+                                }
+                            }
+                        }
+                    });
+                    if !scene_message.is_empty() {
+                        ui.label(&scene_message);
+                    }
+                });
+                //row of pendulums on their own anchors
+                egui::CollapsingHeader::new("Pendulum wave").show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Pendulums");
+                        ui.add(egui::DragValue::new(&mut wave.count).clamp_range(1..=MAX_DULUMS));
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Cycle");
+                        ui.add(egui::DragValue::new(&mut wave.cycle).speed(0.5).clamp_range(0.1..=f64::INFINITY).suffix(" s"))
+                            .on_hover_text("Time until all of them line up again");
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Oscillations");
+                        ui.add(egui::DragValue::new(&mut wave.oscillations).speed(0.1).clamp_range(1.0..=f64::INFINITY))
+                            .on_hover_text("Swings of the longest one per cycle, every next one swings once more");
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Spacing");
+                        ui.add(egui::DragValue::new(&mut wave.spacing).speed(0.01).clamp_range(0.0..=f64::INFINITY));
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Amplitude");
+                        ui.add(egui::DragValue::new(&mut wave.amplitude).speed(0.1).suffix("°"));
+                    });
+                    let gravity = simulation.gravity.magnitude_at(0.0, 0.0);
+                    ui.label(format!(
+                        "Lengths from {:.3} to {:.3}",
+                        wave.length(0, gravity),
+                        wave.length(wave.count.saturating_sub(1), gravity)
+                    ));
+                    if ui.button("Generate").clicked() {
+                        simulation.replace_dulums(wave.dulums(gravity));
+                        palette.apply(&mut simulation.dulums);
+                        simulate = false;
+                        energy_trail.clear();
+                    }
+                });
                 //correct number of dulums
-                if expected_dulums < dulums.len() {
-                    dulums.truncate(expected_dulums);
+                if expected_dulums < simulation.dulums.len() {
+                    simulation.dulums.truncate(expected_dulums);
                 }
-                if expected_dulums > dulums.len() {
-                    for _ in 0..(expected_dulums - dulums.len()) {
-                        let color = DULUMS_COLORS[dulums.len() % DULUMS_COLORS.len()];
-                        dulums.push(Dulum::new(0.0, 2.0, 1.0, false, 100.0, 2.0, color, 0.2));
+                if expected_dulums > simulation.dulums.len() {
+                    for _ in 0..(expected_dulums - simulation.dulums.len()) {
+                        simulation.dulums.push(Dulum::new(0.0, 2.0, 1.0, false, 100.0, 2.0, WHITE, 0.2));
                     }
+                    palette.apply(&mut simulation.dulums);
                 }
 
-                //ovládání pro dula
-                for (ind, dulum) in dulums.iter_mut().enumerate() {
-                    egui::CollapsingHeader::new(format!("Dulum #{}", ind + 1)).show(ui, |ui| {
-                        //dulum's angle
-                        ui.horizontal(|ui| {
-                            ui.label("Angle");
-                            meth::drag_angle(ui, &mut dulum.angle)
+                //gravity field
+                egui::CollapsingHeader::new("Gravity").show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        for (name, magnitude) in GravityField::PRESETS {
+                            if ui.button(name).clicked() {
+                                simulation.gravity = GravityField::Uniform { magnitude, direction: 0.0 };
+                            }
+                        }
+                    });
+
+                    let current = simulation.gravity.name();
+                    let options = [
+                        GravityField::default(),
+                        GravityField::PointMass { position: (0.0, 10.0), strength: 1000.0 },
+                        GravityField::Gradient { magnitude: 9.8, direction: 0.0, gradient: 1.0 },
+                    ];
+                    egui::ComboBox::from_label("Field")
+                        .selected_text(current)
+                        .show_ui(ui, |ui| {
+                            for option in options {
+                                if ui.selectable_label(option.name() == current, option.name()).clicked() && option.name() != current {
+                                    simulation.gravity = option;
+                                }
+                            }
                         });
-                        ui.horizontal(|ui| {
-                            ui.label("Angle Der");
-                            meth::drag_angle(ui, &mut dulum.angle_der)
+
+                    match &mut simulation.gravity {
+                        GravityField::Uniform { magnitude, direction } => {
+                            ui.horizontal(|ui| {
+                                ui.label("Magnitude");
+                                ui.add(egui::DragValue::new(magnitude).speed(0.01));
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label("Direction");
+                                meth::drag_angle(ui, direction)
+                            });
+                        }
+                        GravityField::PointMass { position, strength } => {
+                            ui.horizontal(|ui| {
+                                ui.label("Position");
+                                ui.add(egui::DragValue::new(&mut position.0).speed(0.1));
+                                ui.add(egui::DragValue::new(&mut position.1).speed(0.1));
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label("Strength (GM)");
+                                ui.add(egui::DragValue::new(strength));
+                            });
+                        }
+                        GravityField::Gradient { magnitude, direction, gradient } => {
+                            ui.horizontal(|ui| {
+                                ui.label("Magnitude");
+                                ui.add(egui::DragValue::new(magnitude).speed(0.01));
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label("Direction");
+                                meth::drag_angle(ui, direction)
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label("Gradient");
+                                ui.add(egui::DragValue::new(gradient).speed(0.01).suffix(" 1/s²"));
+                            });
+                        }
+                    }
+                });
+
+                //driven pivot
+                egui::CollapsingHeader::new("Pivot").show(ui, |ui| {
+                    let current = simulation.pivot.motion.name();
+                    let options = [
+                        PivotMotion::Fixed,
+                        PivotMotion::Vertical { amplitude: 0.2, frequency: 20.0 },
+                        PivotMotion::Horizontal { amplitude: 0.5, frequency: 1.0 },
+                        PivotMotion::Expression {
+                            x: Expression::constant(0.0),
+                            y: Expression::constant(0.0),
+                        },
+                        PivotMotion::Mouse { stiffness: 100.0 },
+                    ];
+                    egui::ComboBox::from_label("Motion")
+                        .selected_text(current)
+                        .show_ui(ui, |ui| {
+                            for option in options {
+                                let name = option.name();
+                                if ui.selectable_label(name == current, name).clicked() && name != current {
+                                    simulation.pivot.restart(simulation.time);
+                                    simulation.pivot.motion = option;
+                                    pivot_error.clear();
+                                }
+                            }
                         });
 
-                        //dulum's length
+                    match &mut simulation.pivot.motion {
+                        PivotMotion::Fixed => {}
+                        PivotMotion::Vertical { amplitude, frequency }
+                        | PivotMotion::Horizontal { amplitude, frequency } => {
+                            ui.horizontal(|ui| {
+                                ui.label("Amplitude");
+                                ui.add(egui::DragValue::new(amplitude).speed(0.01));
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label("Frequency");
+                                ui.add(egui::DragValue::new(frequency).speed(0.1).suffix(" Hz"));
+                            });
+                        }
+                        PivotMotion::Expression { x, y } => {
+                            ui.horizontal(|ui| {
+                                ui.label("x(t)");
+                                ui.text_edit_singleline(&mut pivot_expressions.0);
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label("y(t)");
+                                ui.text_edit_singleline(&mut pivot_expressions.1);
+                            });
+                            if ui.button("Apply").clicked() {
+                                match (
+                                    Expression::parse(&pivot_expressions.0),
+                                    Expression::parse(&pivot_expressions.1),
+                                ) {
+                                    (Ok(new_x), Ok(new_y)) => {
+                                        *x = new_x;
+                                        *y = new_y;
+                                        pivot_error.clear();
+                                    }
+                                    (Err(err), _) => pivot_error = format!("x(t): {err}"),
+                                    (_, Err(err)) => pivot_error = format!("y(t): {err}"),
+                                }
+                            }
+                            ui.label(format!("Using x = {}, y = {}", x.source(), y.source()));
+                            if !pivot_error.is_empty() {
+                                ui.label(&pivot_error);
+                            }
+                        }
+                        PivotMotion::Mouse { stiffness } => {
+                            ui.horizontal(|ui| {
+                                ui.label("Stiffness");
+                                ui.add(egui::DragValue::new(stiffness).speed(1.0).clamp_range(0.0..=f64::INFINITY));
+                            });
+                            ui.label("Hold left mouse button while simulating to move the pivot");
+                        }
+                    }
+                });
+
+                //ovládání pro dula
+                egui::CollapsingHeader::new("Dulums").show(ui, |ui| {
+                    //colors
+                    let mut recolor = false;
+                    ui.horizontal(|ui| {
+                        let mut kind = palette;
+                        egui::ComboBox::from_label("Palette")
+                            .selected_text(palette.name())
+                            .show_ui(ui, |ui| {
+                                for option in Palette::ALL {
+                                    if ui.selectable_label(kind.same_kind(&option), option.name()).clicked() {
+                                        kind = option;
+                                    }
+                                }
+                            });
+                        if !kind.same_kind(&palette) {
+                            palette = kind;
+                            recolor = true;
+                        }
+                        if let Palette::Gradient { start, end } = &mut palette {
+                            recolor |= color_button(ui, start).changed();
+                            recolor |= color_button(ui, end).changed();
+                        }
+                        recolor |= ui.button("Apply").clicked();
+                    });
+
+                    //the whole chain at once, e.g. a rope of many short links
+                    ui.horizontal(|ui| {
+                        ui.add(egui::DragValue::new(&mut all_len).speed(0.01).clamp_range(0.001..=f64::INFINITY).prefix("len: "));
+                        ui.add(egui::DragValue::new(&mut all_mass).speed(0.01).clamp_range(0.001..=f64::INFINITY).prefix("mass: "));
+                        if ui.button("Set all").clicked() {
+                            for dulum in &mut simulation.dulums {
+                                dulum.len = all_len;
+                                dulum.default_len = all_len;
+                                dulum.mass = all_mass;
+                            }
+                            recolor = true;
+                        }
+                    });
+
+                    //heat map follows the masses
+                    let edited = dulum_table(ui, &mut simulation.dulums);
+                    if recolor || (edited && palette == Palette::MassHeat) {
+                        palette.apply(&mut simulation.dulums);
+                    }
+                });
+
+                egui::CollapsingHeader::new("Constraints").show(ui, |ui| {
+                    let stabilization = &mut simulation.stabilization;
+                    ui.checkbox(&mut stabilization.baumgarte, "Baumgarte stabilization");
+                    if stabilization.baumgarte {
                         ui.horizontal(|ui| {
-                            ui.label("Length");
-                            ui.add(egui::DragValue::new(&mut dulum.len));
+                            ui.label("Alpha");
+                            ui.add(egui::DragValue::new(&mut stabilization.alpha).speed(0.1).clamp_range(0.0..=f64::INFINITY));
+                            ui.label("Beta");
+                            ui.add(egui::DragValue::new(&mut stabilization.beta).speed(0.1).clamp_range(0.0..=f64::INFINITY));
                         });
-                        ui.horizontal(|ui| {
-                            ui.label("Length Der");
-                            ui.add(egui::DragValue::new(&mut dulum.len_der));
+                    }
+                    ui.checkbox(&mut stabilization.projection, "Project back after every step");
+
+                    //new rods take the current distance
+                    let count = simulation.dulums.len().max(1);
+                    rod_a = rod_a.clamp(1, count);
+                    rod_b = rod_b.clamp(1, count);
+                    let positions = simulation.positions();
+                    let distance = |a: Endpoint, b: Endpoint| {
+                        let position = |endpoint| match endpoint {
+                            Endpoint::Bob(bob) => positions[bob],
+                            Endpoint::Fixed(x, y) => (x, y),
+                        };
+                        let (a, b) = (position(a), position(b));
+                        (a.0 - b.0).hypot(a.1 - b.1)
+                    };
+                    let mut added = None;
+                    ui.horizontal(|ui| {
+                        ui.label("Bob");
+                        ui.add(egui::DragValue::new(&mut rod_a).prefix("#").clamp_range(1..=count));
+                        ui.label("to bob");
+                        ui.add(egui::DragValue::new(&mut rod_b).prefix("#").clamp_range(1..=count));
+                        if ui.button("Add rod").clicked() && rod_a != rod_b {
+                            added = Some((Endpoint::Bob(rod_a - 1), Endpoint::Bob(rod_b - 1)));
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Bob");
+                        ui.add(egui::DragValue::new(&mut rod_a).prefix("#").clamp_range(1..=count));
+                        ui.label("to point");
+                        ui.add(egui::DragValue::new(&mut rod_point.0).speed(0.05).prefix("x: "));
+                        ui.add(egui::DragValue::new(&mut rod_point.1).speed(0.05).prefix("y: "));
+                        if ui.button("Add rod").clicked() {
+                            added = Some((Endpoint::Bob(rod_a - 1), Endpoint::Fixed(rod_point.0, rod_point.1)));
+                        }
+                    });
+                    if let Some((a, b)) = added.filter(|_| !positions.is_empty()) {
+                        let length = distance(a, b);
+                        simulation.constraints.push(Constraint::Rod { a, b, length });
+                    }
+                    if ui.button(format!("Pin bob #{rod_a} where it is")).clicked() && !positions.is_empty() {
+                        simulation.constraints.push(Constraint::Pin {
+                            bob: rod_a - 1,
+                            position: positions[rod_a - 1],
                         });
+                    }
 
+                    let mut removed = None;
+                    for (ind, constraint) in simulation.constraints.iter_mut().enumerate() {
                         ui.horizontal(|ui| {
-                            ui.label("Mass");
-                            ui.add(egui::DragValue::new(&mut dulum.mass));
+                            match constraint {
+                                Constraint::Rod { a, b, length } => {
+                                    ui.label(format!("Rod {} to {}", a.label(), b.label()));
+                                    ui.add(egui::DragValue::new(length).speed(0.01).clamp_range(0.0..=f64::INFINITY).prefix("length: "));
+                                }
+                                Constraint::Pin { bob, position } => {
+                                    ui.label(format!("Pin #{} at ({:.2}, {:.2})", *bob + 1, position.0, position.1));
+                                }
+                            }
+                            if ui.button("Remove").clicked() {
+                                removed = Some(ind);
+                            }
                         });
+                    }
+                    if let Some(ind) = removed {
+                        simulation.constraints.remove(ind);
+                    }
 
-                        //elastic
-                        ui.checkbox(&mut dulum.elastic, "Elastic");
-                        ui.checkbox(&mut dulum.push_elastic, "Push elastic");
+                    let violation = simulation.constraint_violation();
+                    ui.label(format!("Largest violation: {violation:.2e} m"));
+                    if violation_trail.len() >= 1024 {
+                        violation_trail.pop_front();
+                    }
+                    violation_trail.push_back(violation);
 
-                        //hardness
-                        ui.horizontal(|ui| {
-                            ui.label("Hardness");
-                            ui.add(egui::DragValue::new(&mut dulum.hardness));
-                        });
+                    let points: PlotPoints = violation_trail
+                        .iter()
+                        .enumerate()
+                        .map(|(x, y)| [x as f64, *y])
+                        .collect();
+                    egui::plot::Plot::new("Constraint violation")
+                        .view_aspect(2.0)
+                        .show(ui, |plot| plot.line(Line::new(points)));
+                });
 
+                egui::CollapsingHeader::new("Springs").show(ui, |ui| {
+                    //new springs start relaxed at the current distance
+                    let count = simulation.dulums.len().max(1);
+                    spring_a = spring_a.clamp(1, count);
+                    spring_b = spring_b.clamp(1, count);
+                    let positions = simulation.positions();
+                    let distance = |a: Endpoint, b: Endpoint| {
+                        let position = |endpoint| match endpoint {
+                            Endpoint::Bob(bob) => positions[bob],
+                            Endpoint::Fixed(x, y) => (x, y),
+                        };
+                        let (a, b) = (position(a), position(b));
+                        (a.0 - b.0).hypot(a.1 - b.1)
+                    };
+                    ui.horizontal(|ui| {
+                        ui.label("Hardness");
+                        ui.add(egui::DragValue::new(&mut spring_hardness).speed(1.0).clamp_range(0.0..=f64::INFINITY));
+                    });
+                    let mut added = None;
+                    ui.horizontal(|ui| {
+                        ui.label("Bob");
+                        ui.add(egui::DragValue::new(&mut spring_a).prefix("#").clamp_range(1..=count));
+                        ui.label("to bob");
+                        ui.add(egui::DragValue::new(&mut spring_b).prefix("#").clamp_range(1..=count));
+                        if ui.button("Add spring").clicked() && spring_a != spring_b {
+                            added = Some((Endpoint::Bob(spring_a - 1), Endpoint::Bob(spring_b - 1)));
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Bob");
+                        ui.add(egui::DragValue::new(&mut spring_a).prefix("#").clamp_range(1..=count));
+                        ui.label("to point");
+                        ui.add(egui::DragValue::new(&mut spring_point.0).speed(0.05).prefix("x: "));
+                        ui.add(egui::DragValue::new(&mut spring_point.1).speed(0.05).prefix("y: "));
+                        if ui.button("Add spring").clicked() {
+                            added = Some((Endpoint::Bob(spring_a - 1), Endpoint::Fixed(spring_point.0, spring_point.1)));
+                        }
+                    });
+                    if let Some((a, b)) = added.filter(|_| !positions.is_empty()) {
+                        simulation.springs.push(Spring::new(a, b, spring_hardness, distance(a, b)));
+                    }
+
+                    let mut removed = None;
+                    for (ind, spring) in simulation.springs.iter_mut().enumerate() {
                         ui.horizontal(|ui| {
-                            ui.label("Default Len");
-                            ui.add(egui::DragValue::new(&mut dulum.default_len));
+                            ui.label(format!("{} to {}", spring.a.label(), spring.b.label()));
+                            ui.add(egui::DragValue::new(&mut spring.hardness).speed(1.0).clamp_range(0.0..=f64::INFINITY).prefix("k: "));
+                            ui.add(egui::DragValue::new(&mut spring.default_len).speed(0.01).clamp_range(0.0..=f64::INFINITY).prefix("len: "));
+                            ui.add(egui::DragValue::new(&mut spring.damping).speed(0.01).clamp_range(0.0..=f64::INFINITY).prefix("damping: "));
+                            ui.checkbox(&mut spring.push_elastic, "Push");
+                            if ui.button("Remove").clicked() {
+                                removed = Some(ind);
+                            }
                         });
+                    }
+                    if let Some(ind) = removed {
+                        simulation.springs.remove(ind);
+                    }
+                });
 
-                        //visibility
-                        ui.checkbox(&mut dulum.visible, "Show dulum");
-                        ui.checkbox(&mut dulum.visible_line, "Show line");
-                        ui.checkbox(&mut dulum.visible_trace, "Show trace");
+                egui::CollapsingHeader::new("Collisions").show(ui, |ui| {
+                    let collisions = &mut simulation.collisions;
+                    ui.checkbox(&mut collisions.bobs, "Bobs collide with each other");
+                    ui.add(egui::Slider::new(&mut collisions.restitution, 0.0..=1.0).text("Restitution"));
+                    ui.label(format!("Collisions: {}", collisions.events));
+
+                    //new obstacles show up below the pivot, they are easy to move from there
+                    ui.horizontal(|ui| {
+                        if ui.button("Add wall").clicked() {
+                            collisions.obstacles.push(Obstacle::Wall { x: 2.0 });
+                        }
+                        if ui.button("Add floor").clicked() {
+                            collisions.obstacles.push(Obstacle::Floor { y: 3.0 });
+                        }
+                        if ui.button("Add circle").clicked() {
+                            collisions.obstacles.push(Obstacle::Circle { center: (0.0, 3.0), radius: 0.5 });
+                        }
                     });
-                }
+
+                    let mut removed = None;
+                    for (ind, obstacle) in collisions.obstacles.iter_mut().enumerate() {
+                        ui.horizontal(|ui| {
+                            ui.label(obstacle.name());
+                            match obstacle {
+                                Obstacle::Wall { x } => {
+                                    ui.add(egui::DragValue::new(x).speed(0.05).prefix("x: "));
+                                }
+                                Obstacle::Floor { y } => {
+                                    ui.add(egui::DragValue::new(y).speed(0.05).prefix("y: "));
+                                }
+                                Obstacle::Circle { center, radius } => {
+                                    ui.add(egui::DragValue::new(&mut center.0).speed(0.05).prefix("x: "));
+                                    ui.add(egui::DragValue::new(&mut center.1).speed(0.05).prefix("y: "));
+                                    ui.add(egui::DragValue::new(radius).speed(0.01).clamp_range(0.0..=f64::INFINITY).prefix("r: "));
+                                }
+                            }
+                            if ui.button("Remove").clicked() {
+                                removed = Some(ind);
+                            }
+                        });
+                    }
+                    if let Some(ind) = removed {
+                        collisions.obstacles.remove(ind);
+                    }
+                });
 
                 //energy handling
                 egui::CollapsingHeader::new("Energy")
@@ -349,29 +1480,34 @@ async fn main() {
                         });
 
 
-                        let mut x = 0.0;
-                        let mut y = 0.0;
-                        let mut ultra_total = 0.0;
-
-                        for (ind, dulum) in dulums.iter().enumerate() {
-                            let pot_grav = dulum.potential_gravity_energy(x, y);
-                            x = pot_grav.1;
-                            y = pot_grav.2;
+                        let energies = simulation.energies();
+                        let spring_energy = simulation.spring_energy();
+                        let ultra_total: f64 = energies.iter().map(|x| x.total()).sum::<f64>() + spring_energy;
 
-                            let pot_elas = dulum.potential_elastic_energy();
-                            let kinet = dulum.kinetic_energy();
-
-                            let total = pot_grav.0 + pot_elas + kinet;
-                            ultra_total += total;
-
-                            egui::CollapsingHeader::new(format!("Dulum {}", ind))   
-                                .show(ui, |ui| {
-                                    ui.label(format!("Pot Grav: {:.2}", pot_grav.0).as_str());
-                                    ui.label(format!("Pot Elas: {:.2}", pot_elas).as_str());
-                                    ui.label(format!("En Kinet: {:.2}", kinet).as_str());
-                                    ui.label(format!("Total: {:.2}", total).as_str());
+                        let row_height = table_row_height(ui);
+                        table_header(ui, "Energies header", &["#", "Pot Grav", "Pot Elas", "En Kinet", "Total"]);
+                        egui::ScrollArea::vertical()
+                            .max_height(200.0)
+                            .id_source("Energy table")
+                            .show_rows(ui, row_height, energies.len(), |ui, rows| {
+                                table_grid("Energies").show(ui, |ui| {
+                                    for ind in rows {
+                                        let energy = &energies[ind];
+                                        ui.label(format!("{}", ind + 1));
+                                        ui.label(format!("{:.2}", energy.potential_gravity));
+                                        ui.label(format!("{:.2}", energy.potential_elastic));
+                                        ui.label(format!("{:.2}", energy.kinetic));
+                                        ui.label(format!("{:.2}", energy.total()));
+                                        ui.end_row();
+                                    }
+                                });
                             });
+
+                        if !simulation.springs.is_empty() {
+                            ui.label(format!("Springs: {spring_energy:.2}"));
                         }
+                        ui.label(format!("Dissipated: {:.2}", simulation.dissipated).as_str());
+                        ui.label(format!("Total + Dissipated: {:.2}", ultra_total + simulation.dissipated).as_str());
 
                         //přidej ultra total 
                         if energy_trail.len() >= 1024 {
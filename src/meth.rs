@@ -1,4 +1,5 @@
 use egui::Ui;
+use nalgebra::DMatrix;
 
 pub fn deg2rad(deg: f64) -> f64 {
     std::f64::consts::PI / 180.0 * deg
@@ -30,3 +31,36 @@ pub fn drag_angle(ui: &mut Ui, radians: &mut f64) -> egui::Response {
 
     response
 }
+
+///Hooks law shared by elastic dulums and springs, force along the spring, negative makes it shorter,
+///push_elastic springs go slack instead of pushing when shorter than default_len
+pub fn hooks_force(hardness: f64, default_len: f64, push_elastic: bool, len: f64) -> f64 {
+    if push_elastic && len < default_len {
+        0.0
+    } else {
+        -hardness * (len - default_len)
+    }
+}
+
+///Energy stored in a spring following hooks_force
+pub fn hooks_energy(hardness: f64, default_len: f64, push_elastic: bool, len: f64) -> f64 {
+    if push_elastic && len < default_len {
+        0.0
+    } else {
+        hardness * (len - default_len).powi(2) / 2.0
+    }
+}
+
+///Damping of a spring getting longer at rate, works against the rate
+pub fn spring_damping_force(damping: f64, rate: f64) -> f64 {
+    -damping * rate
+}
+
+///Solves matrix * x = right, Cholesky for the symmetric positive definite mass matrices,
+///LU if that fails, None if the matrix is singular
+pub fn solve(matrix: &DMatrix<f64>, right: &DMatrix<f64>) -> Option<DMatrix<f64>> {
+    if let Some(cholesky) = matrix.clone().cholesky() {
+        return Some(cholesky.solve(right));
+    }
+    matrix.clone().lu().solve(right)
+}
@@ -1,5 +1,10 @@
+use std::collections::VecDeque;
+
 use macroquad::prelude::*;
 
+//how far back the throw velocity looks, long enough to smooth out the frame time jitter
+const THROW_WINDOW: f64 = 0.05;
+
 pub struct MouseMovement {
     pub last_mousex: f32,
     pub last_mousey: f32,
@@ -9,6 +14,22 @@ pub struct MouseMovement {
 
     pub dx: f32,
     pub dy: f32,
+
+    //same thing, but in world space of the last camera passed to update_world
+    pub world_x: f32,
+    pub world_y: f32,
+
+    pub world_dx: f32,
+    pub world_dy: f32,
+
+    //(time, x, y) of the cursor on the screen in the last THROW_WINDOW
+    history: VecDeque<(f64, f32, f32)>,
+}
+
+impl Default for MouseMovement {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl MouseMovement {
@@ -22,6 +43,11 @@ impl MouseMovement {
             mousey: my,
             dx: 0.0,
             dy: 0.0,
+            world_x: 0.0,
+            world_y: 0.0,
+            world_dx: 0.0,
+            world_dy: 0.0,
+            history: VecDeque::from([(get_time(), mx, my)]),
         }
     }
 
@@ -43,5 +69,37 @@ impl MouseMovement {
         self.dx *= 2.0; //TODO: fixni to dpi ve více rozumném způsobu (pokud za to vůbec může dpi...)
         self.dy *= 2.0; //TODO: fixni to dpi ve více rozumném způsobu (pokud za to vůbec může dpi...)
                         //nemůže, může za to ten fakt, že máme rozsah -1..1 a ne 0..1, což má celkem délku 2 a ne 1
+
+        //keep one sample from before the window so it spans all of it even on slow frames
+        let now = get_time();
+        self.history.push_back((now, mx, my));
+        while self.history.len() > 2 && self.history[1].0 <= now - THROW_WINDOW {
+            self.history.pop_front();
+        }
+    }
+
+    ///Velocity of the cursor over the last few frames in the units of dx per second,
+    ///divide by the camera scale for world units, panning and zooming don't move it
+    pub fn velocity(&self) -> (f32, f32) {
+        let (Some(&(start, x0, y0)), Some(&(end, x1, y1))) =
+            (self.history.front(), self.history.back())
+        else {
+            return (0.0, 0.0);
+        };
+        let duration = (end - start) as f32;
+        if duration <= 0.0 {
+            return (0.0, 0.0);
+        }
+        ((x1 - x0) * 2.0 / duration, (y1 - y0) * 2.0 / duration)
+    }
+
+    pub fn update_world(&mut self, camera: &Camera2D) {
+        let world = camera.screen_to_world(vec2(self.mousex, self.mousey));
+
+        self.world_dx = world.x - self.world_x;
+        self.world_dy = world.y - self.world_y;
+
+        self.world_x = world.x;
+        self.world_y = world.y;
     }
 }
@@ -0,0 +1,86 @@
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{dulum::Dulum, simulation::DULUMS_COLORS};
+
+///How the dulums of a chain get their colors, works for any length of the chain
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum Palette {
+    ///Repeats the six original colors
+    #[default]
+    Cycle,
+    ///Blends from the first dulum to the last one
+    Gradient {
+        #[serde(with = "crate::scene::color")]
+        start: Color,
+        #[serde(with = "crate::scene::color")]
+        end: Color,
+    },
+    ///Goes around the hue circle along the chain
+    Rainbow,
+    ///Light dulums blue, heavy ones red
+    MassHeat,
+}
+
+impl Palette {
+    pub const ALL: [Palette; 4] = [
+        Palette::Cycle,
+        Palette::Gradient {
+            start: RED,
+            end: BLUE,
+        },
+        Palette::Rainbow,
+        Palette::MassHeat,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Palette::Cycle => "Cycle",
+            Palette::Gradient { .. } => "Gradient",
+            Palette::Rainbow => "Rainbow",
+            Palette::MassHeat => "Mass heat map",
+        }
+    }
+
+    ///Same kind of palette, gradients compare equal whatever their colors
+    pub fn same_kind(&self, other: &Palette) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+
+    ///Recolors the whole chain
+    pub fn apply(&self, dulums: &mut [Dulum]) {
+        let masses = dulums.iter().map(|x| x.mass);
+        let lightest = masses.clone().fold(f64::INFINITY, f64::min);
+        let heaviest = masses.fold(f64::NEG_INFINITY, f64::max);
+        let count = dulums.len();
+
+        for (ind, dulum) in dulums.iter_mut().enumerate() {
+            //position along the chain from 0 to 1
+            let along = if count > 1 {
+                ind as f32 / (count - 1) as f32
+            } else {
+                0.0
+            };
+
+            dulum.color = match self {
+                Palette::Cycle => DULUMS_COLORS[ind % DULUMS_COLORS.len()],
+                Palette::Gradient { start, end } => Color::new(
+                    start.r + (end.r - start.r) * along,
+                    start.g + (end.g - start.g) * along,
+                    start.b + (end.b - start.b) * along,
+                    start.a + (end.a - start.a) * along,
+                ),
+                //stops before red comes around again
+                Palette::Rainbow => macroquad::color::hsl_to_rgb(0.85 * along, 1.0, 0.5),
+                Palette::MassHeat => {
+                    let heat = if heaviest > lightest {
+                        ((dulum.mass - lightest) / (heaviest - lightest)) as f32
+                    } else {
+                        0.5
+                    };
+                    macroquad::color::hsl_to_rgb(0.66 * (1.0 - heat), 1.0, 0.5)
+                }
+            };
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{meth::normalize_angle, simulation::Simulation};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Quantity {
+    Angle,
+    AngleDer,
+    Len,
+    LenDer,
+    X,
+    Y,
+}
+
+impl Quantity {
+    pub const ALL: [Quantity; 6] = [
+        Quantity::Angle,
+        Quantity::AngleDer,
+        Quantity::Len,
+        Quantity::LenDer,
+        Quantity::X,
+        Quantity::Y,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Quantity::Angle => "angle",
+            Quantity::AngleDer => "angle_der",
+            Quantity::Len => "len",
+            Quantity::LenDer => "len_der",
+            Quantity::X => "x",
+            Quantity::Y => "y",
+        }
+    }
+}
+
+///One quantity of one dulum
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Coordinate {
+    pub dulum: usize,
+    pub quantity: Quantity,
+}
+
+impl Coordinate {
+    pub fn new(dulum: usize, quantity: Quantity) -> Self {
+        Self { dulum, quantity }
+    }
+
+    ///None if the dulum does not exist
+    pub fn value(&self, simulation: &Simulation) -> Option<f64> {
+        let dulum = simulation.dulums.get(self.dulum)?;
+
+        Some(match self.quantity {
+            Quantity::Angle => dulum.angle,
+            Quantity::AngleDer => dulum.angle_der,
+            Quantity::Len => dulum.len,
+            Quantity::LenDer => dulum.len_der,
+            Quantity::X => simulation.positions()[self.dulum].0,
+            Quantity::Y => simulation.positions()[self.dulum].1,
+        })
+    }
+
+    pub fn label(&self) -> String {
+        format!("{} #{}", self.quantity.name(), self.dulum + 1)
+    }
+
+    ///Same names as the CSV columns of headless, e.g. `angle_der_2`
+    pub fn from_key(key: &str) -> Option<Self> {
+        let (name, number) = key.rsplit_once('_')?;
+        let number: usize = number.parse().ok()?;
+        let quantity = Quantity::ALL.into_iter().find(|x| x.name() == name)?;
+
+        Some(Self::new(number.checked_sub(1)?, quantity))
+    }
+}
+
+///Recent history of two coordinates against each other
+pub struct PhaseTrail {
+    pub x: Coordinate,
+    pub y: Coordinate,
+    ///Angles go through normalize_angle
+    pub wrap: bool,
+    pub history: usize,
+
+    points: VecDeque<[f64; 2]>,
+}
+
+impl PhaseTrail {
+    pub fn new(x: Coordinate, y: Coordinate) -> Self {
+        Self {
+            x,
+            y,
+            wrap: false,
+            history: 2048,
+            points: VecDeque::with_capacity(2048),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.points.clear();
+    }
+
+    pub fn record(&mut self, simulation: &Simulation) {
+        let (Some(x), Some(y)) = (self.x.value(simulation), self.y.value(simulation)) else {
+            return;
+        };
+
+        while self.points.len() >= self.history.max(1) {
+            self.points.pop_front();
+        }
+        self.points
+            .push_back([self.wrapped(self.x, x), self.wrapped(self.y, y)]);
+    }
+
+    fn wrapped(&self, coordinate: Coordinate, value: f64) -> f64 {
+        if self.wrap && coordinate.quantity == Quantity::Angle {
+            normalize_angle(value)
+        } else {
+            value
+        }
+    }
+
+    ///The trail cut into pieces wherever a wrapped angle jumped around the circle
+    pub fn segments(&self) -> Vec<Vec<[f64; 2]>> {
+        let mut segments = vec![Vec::new()];
+
+        for point in &self.points {
+            let current = segments.last_mut().unwrap();
+            let jumped = current.last().is_some_and(|last: &[f64; 2]| {
+                self.wrap
+                    && ((self.x.quantity == Quantity::Angle
+                        && (point[0] - last[0]).abs() > std::f64::consts::PI)
+                        || (self.y.quantity == Quantity::Angle
+                            && (point[1] - last[1]).abs() > std::f64::consts::PI))
+            });
+
+            if jumped {
+                segments.push(vec![*point]);
+            } else {
+                current.push(*point);
+            }
+        }
+
+        segments
+    }
+}
@@ -0,0 +1,176 @@
+use serde::{Deserialize, Serialize};
+
+use crate::expression::Expression;
+
+//step of the central differences used on expressions
+const DIFFERENCE_STEP: f64 = 1e-4;
+
+///Prescribed motion of the point the chain hangs from
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub enum PivotMotion {
+    #[default]
+    Fixed,
+    ///y = amplitude * sin(2 pi frequency t)
+    Vertical { amplitude: f64, frequency: f64 },
+    ///x = amplitude * sin(2 pi frequency t)
+    Horizontal { amplitude: f64, frequency: f64 },
+    ///Any function of time for both coordinates
+    Expression { x: Expression, y: Expression },
+    ///Follows the target with a critically damped spring
+    Mouse { stiffness: f64 },
+}
+
+impl PivotMotion {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PivotMotion::Fixed => "Fixed",
+            PivotMotion::Vertical { .. } => "Vertical oscillation",
+            PivotMotion::Horizontal { .. } => "Horizontal oscillation",
+            PivotMotion::Expression { .. } => "Expression of time",
+            PivotMotion::Mouse { .. } => "Mouse",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Kinematics {
+    pub position: (f64, f64),
+    pub velocity: (f64, f64),
+    pub acceleration: (f64, f64),
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Pivot {
+    pub motion: PivotMotion,
+    ///Where the mouse driven pivot wants to be
+    #[serde(skip)]
+    pub target: (f64, f64),
+
+    //state of the mouse follower at `since`, its acceleration is held until the next advance
+    #[serde(skip)]
+    follower: Kinematics,
+    #[serde(skip)]
+    since: f64,
+}
+
+impl Pivot {
+    pub fn new(motion: PivotMotion) -> Self {
+        Self {
+            motion,
+            ..Default::default()
+        }
+    }
+
+    pub fn kinematics(&self, time: f64) -> Kinematics {
+        match &self.motion {
+            PivotMotion::Fixed => Kinematics::default(),
+            PivotMotion::Vertical {
+                amplitude,
+                frequency,
+            } => {
+                let (position, velocity, acceleration) = harmonic(*amplitude, *frequency, time);
+                Kinematics {
+                    position: (0.0, position),
+                    velocity: (0.0, velocity),
+                    acceleration: (0.0, acceleration),
+                }
+            }
+            PivotMotion::Horizontal {
+                amplitude,
+                frequency,
+            } => {
+                let (position, velocity, acceleration) = harmonic(*amplitude, *frequency, time);
+                Kinematics {
+                    position: (position, 0.0),
+                    velocity: (velocity, 0.0),
+                    acceleration: (acceleration, 0.0),
+                }
+            }
+            PivotMotion::Expression { x, y } => {
+                let (x, vx, ax) = differentiate(x, time);
+                let (y, vy, ay) = differentiate(y, time);
+                Kinematics {
+                    position: (x, y),
+                    velocity: (vx, vy),
+                    acceleration: (ax, ay),
+                }
+            }
+            PivotMotion::Mouse { .. } => {
+                let tau = time - self.since;
+                let Kinematics {
+                    position: (x, y),
+                    velocity: (vx, vy),
+                    acceleration: (ax, ay),
+                } = self.follower;
+
+                Kinematics {
+                    position: (
+                        x + vx * tau + ax * tau * tau / 2.0,
+                        y + vy * tau + ay * tau * tau / 2.0,
+                    ),
+                    velocity: (vx + ax * tau, vy + ay * tau),
+                    acceleration: (ax, ay),
+                }
+            }
+        }
+    }
+
+    ///Stops the mouse follower where it is and restarts its clock at time
+    pub fn restart(&mut self, time: f64) {
+        self.follower = Kinematics {
+            position: self.kinematics(self.since).position,
+            ..Default::default()
+        };
+        self.since = time;
+    }
+
+    ///Moves the mouse follower to the time, other motions only depend on time
+    pub fn advance(&mut self, time: f64) {
+        let PivotMotion::Mouse { stiffness } = self.motion else {
+            return;
+        };
+
+        let Kinematics {
+            position: (x, y),
+            velocity: (vx, vy),
+            ..
+        } = self.kinematics(time);
+        let damping = 2.0 * stiffness.sqrt();
+
+        self.follower = Kinematics {
+            position: (x, y),
+            velocity: (vx, vy),
+            acceleration: (
+                stiffness * (self.target.0 - x) - damping * vx,
+                stiffness * (self.target.1 - y) - damping * vy,
+            ),
+        };
+        self.since = time;
+    }
+}
+
+fn harmonic(amplitude: f64, frequency: f64, time: f64) -> (f64, f64, f64) {
+    let omega = 2.0 * std::f64::consts::PI * frequency;
+    let (sin, cos) = (omega * time).sin_cos();
+
+    (
+        amplitude * sin,
+        amplitude * omega * cos,
+        -amplitude * omega * omega * sin,
+    )
+}
+
+fn differentiate(expression: &Expression, time: f64) -> (f64, f64, f64) {
+    let h = DIFFERENCE_STEP;
+    let (before, now, after) = (
+        expression.eval(time - h),
+        expression.eval(time),
+        expression.eval(time + h),
+    );
+
+    (
+        now,
+        (after - before) / (2.0 * h),
+        (after - 2.0 * now + before) / (h * h),
+    )
+}
@@ -0,0 +1,148 @@
+use std::f64::consts::PI;
+
+use crate::{
+    dulum::{Dulum, Parent},
+    meth::deg2rad,
+    simulation::DULUMS_COLORS,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Preset {
+    SmallAngle,
+    DoublePendulum,
+    TriplePendulum,
+    SwingingSpring,
+    PushChain,
+    SixChain,
+}
+
+impl Preset {
+    pub const ALL: [Preset; 6] = [
+        Preset::SmallAngle,
+        Preset::DoublePendulum,
+        Preset::TriplePendulum,
+        Preset::SwingingSpring,
+        Preset::PushChain,
+        Preset::SixChain,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Preset::SmallAngle => "Single pendulum, small angle",
+            Preset::DoublePendulum => "Chaotic double pendulum",
+            Preset::TriplePendulum => "Triple pendulum",
+            Preset::SwingingSpring => "Swinging spring (2:1 resonance)",
+            Preset::PushChain => "Push elastic chain",
+            Preset::SixChain => "Six link chain",
+        }
+    }
+
+    ///Short name used on the command line
+    pub fn key(&self) -> &'static str {
+        match self {
+            Preset::SmallAngle => "small-angle",
+            Preset::DoublePendulum => "double",
+            Preset::TriplePendulum => "triple",
+            Preset::SwingingSpring => "swinging-spring",
+            Preset::PushChain => "push-chain",
+            Preset::SixChain => "six-chain",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Preset> {
+        Preset::ALL.into_iter().find(|x| x.key() == key)
+    }
+
+    ///Builds the chain, strength of gravity is needed to tune the springs
+    pub fn dulums(&self, gravity: f64) -> Vec<Dulum> {
+        match self {
+            Preset::SmallAngle => vec![rigid(0, 10.0, 2.0, 1.0)],
+            Preset::DoublePendulum => vec![rigid(0, 120.0, 1.5, 1.0), rigid(1, 60.0, 1.5, 1.0)],
+            Preset::TriplePendulum => vec![
+                rigid(0, 100.0, 1.0, 1.0),
+                rigid(1, 120.0, 1.0, 1.0),
+                rigid(2, 140.0, 1.0, 1.0),
+            ],
+            Preset::SwingingSpring => {
+                //the spring oscillates twice as fast as the pendulum swings:
+                //k / m = 4 g / L where L = L0 + m g / k is the stretched length, so k = 3 m g / L0
+                let (mass, default_len) = (1.0, 2.0);
+                let hardness = 3.0 * mass * gravity / default_len;
+                let stretched = default_len + mass * gravity / hardness;
+
+                let mut dulum = rigid(0, 5.0, stretched + 0.3, mass);
+                dulum.elastic = true;
+                dulum.hardness = hardness;
+                dulum.default_len = default_len;
+                vec![dulum]
+            }
+            Preset::PushChain => (0..4)
+                .map(|i| {
+                    let mut dulum = rigid(i, 90.0, 1.0, 0.5);
+                    dulum.elastic = true;
+                    dulum.push_elastic = true;
+                    dulum.hardness = 500.0;
+                    dulum.default_len = 1.0;
+                    dulum
+                })
+                .collect(),
+            Preset::SixChain => (0..DULUMS_COLORS.len())
+                .map(|i| rigid(i, 90.0, 1.0, 1.0))
+                .collect(),
+        }
+    }
+}
+
+///Row of pendulums side by side, each on its own anchor, in one cycle the longest one
+///swings oscillations times and every next one once more, so they drift in and out of phase
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PendulumWave {
+    pub count: usize,
+    ///Time until all of them line up again
+    pub cycle: f64,
+    ///Swings of the longest pendulum in one cycle
+    pub oscillations: f64,
+    ///Distance between neighbouring anchors
+    pub spacing: f64,
+    ///Starting angle of all of them, in degrees
+    pub amplitude: f64,
+}
+
+impl Default for PendulumWave {
+    fn default() -> Self {
+        Self {
+            count: 15,
+            cycle: 60.0,
+            oscillations: 20.0,
+            spacing: 0.4,
+            amplitude: 15.0,
+        }
+    }
+}
+
+impl PendulumWave {
+    ///Length of the pendulum at index which swings with its period,
+    ///small angle formula, so bigger amplitudes line up a bit late
+    pub fn length(&self, index: usize, gravity: f64) -> f64 {
+        let period = self.cycle / (self.oscillations + index as f64);
+        gravity * (period / (2.0 * PI)).powi(2)
+    }
+
+    pub fn dulums(&self, gravity: f64) -> Vec<Dulum> {
+        let middle = self.count.saturating_sub(1) as f64 / 2.0;
+
+        (0..self.count)
+            .map(|i| {
+                let mut dulum = rigid(i, self.amplitude, self.length(i, gravity), 1.0);
+                dulum.parent = Parent::Pivot;
+                dulum.anchor = ((i as f64 - middle) * self.spacing, 0.0);
+                dulum
+            })
+            .collect()
+    }
+}
+
+fn rigid(index: usize, angle: f64, len: f64, mass: f64) -> Dulum {
+    let color = DULUMS_COLORS[index % DULUMS_COLORS.len()];
+    Dulum::new(deg2rad(angle), len, mass, false, 100.0, len, color, 0.2)
+}
@@ -0,0 +1,199 @@
+use std::{fmt, fs, io, path::Path};
+
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    collision::Collisions,
+    constraint::{Constraint, Stabilization},
+    dulum::Dulum,
+    gravity::GravityField,
+    integrator::IntegratorKind,
+    palette::Palette,
+    pivot::Pivot,
+    simulation::Simulation,
+    solver::SolverKind,
+    spring::Spring,
+};
+
+///Everything needed to restore a setup, saved as RON or JSON depending on the extension
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Scene {
+    pub camera_scale: f32,
+    pub camera_origin: (f32, f32),
+
+    pub gravity: GravityField,
+    pub time_step: f32,
+    pub integrator: IntegratorKind,
+    ///Absolute and relative tolerance of adaptive integrators
+    #[serde(default)]
+    pub tolerances: Option<(f64, f64)>,
+    #[serde(default)]
+    pub solver: SolverKind,
+    #[serde(default)]
+    pub pivot: Pivot,
+
+    pub dulums: Vec<Dulum>,
+    #[serde(default)]
+    pub constraints: Vec<Constraint>,
+    #[serde(default)]
+    pub stabilization: Stabilization,
+    #[serde(default)]
+    pub springs: Vec<Spring>,
+    #[serde(default)]
+    pub collisions: Collisions,
+    #[serde(default)]
+    pub palette: Palette,
+}
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    Ron(ron::Error),
+    UnknownFormat(String),
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneError::Io(err) => write!(f, "{err}"),
+            SceneError::Json(err) => write!(f, "invalid JSON: {err}"),
+            SceneError::Ron(err) => write!(f, "invalid RON: {err}"),
+            SceneError::UnknownFormat(path) => {
+                write!(f, "'{path}' has to end with .ron or .json")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+impl From<io::Error> for SceneError {
+    fn from(err: io::Error) -> Self {
+        SceneError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for SceneError {
+    fn from(err: serde_json::Error) -> Self {
+        SceneError::Json(err)
+    }
+}
+
+impl From<ron::Error> for SceneError {
+    fn from(err: ron::Error) -> Self {
+        SceneError::Ron(err)
+    }
+}
+
+impl From<ron::error::SpannedError> for SceneError {
+    fn from(err: ron::error::SpannedError) -> Self {
+        SceneError::Ron(err.code)
+    }
+}
+
+enum Format {
+    Ron,
+    Json,
+}
+
+fn format_of(path: &Path) -> Result<Format, SceneError> {
+    match path.extension().and_then(|x| x.to_str()) {
+        Some("ron") => Ok(Format::Ron),
+        Some("json") => Ok(Format::Json),
+        _ => Err(SceneError::UnknownFormat(path.display().to_string())),
+    }
+}
+
+impl Scene {
+    pub fn capture(
+        simulation: &Simulation,
+        palette: Palette,
+        camera_scale: f32,
+        camera_origin: Vec2,
+    ) -> Self {
+        let mut integrator = simulation.integrator.clone();
+        let tolerances = integrator
+            .error_control()
+            .map(|control| (control.absolute_tolerance, control.relative_tolerance));
+
+        Self {
+            camera_scale,
+            camera_origin: (camera_origin.x, camera_origin.y),
+            gravity: simulation.gravity,
+            time_step: simulation.time_step,
+            integrator: simulation.integrator.kind(),
+            tolerances,
+            solver: simulation.solver,
+            pivot: simulation.pivot.clone(),
+            dulums: simulation.dulums.clone(),
+            constraints: simulation.constraints.clone(),
+            stabilization: simulation.stabilization,
+            springs: simulation.springs.clone(),
+            collisions: simulation.collisions.clone(),
+            palette,
+        }
+    }
+
+    pub fn to_simulation(&self) -> Simulation {
+        let mut simulation = Simulation::new(self.dulums.clone());
+        simulation.gravity = self.gravity;
+        simulation.time_step = self.time_step;
+        simulation.integrator = self.integrator.build();
+        if let (Some((absolute, relative)), Some(control)) =
+            (self.tolerances, simulation.integrator.error_control())
+        {
+            control.absolute_tolerance = absolute;
+            control.relative_tolerance = relative;
+        }
+        simulation.solver = self.solver;
+        simulation.pivot = self.pivot.clone();
+        simulation.constraints = self.constraints.clone();
+        simulation.stabilization = self.stabilization;
+        simulation.springs = self.springs.clone();
+        simulation.collisions = self.collisions.clone();
+        simulation
+    }
+
+    pub fn camera_origin(&self) -> Vec2 {
+        vec2(self.camera_origin.0, self.camera_origin.1)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SceneError> {
+        let path = path.as_ref();
+        let format = format_of(path)?;
+        let content = fs::read_to_string(path)?;
+
+        Ok(match format {
+            Format::Ron => ron::from_str(&content)?,
+            Format::Json => serde_json::from_str(&content)?,
+        })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SceneError> {
+        let path = path.as_ref();
+        let content = match format_of(path)? {
+            Format::Ron => ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?,
+            Format::Json => serde_json::to_string_pretty(self)?,
+        };
+
+        fs::write(path, content)?;
+        Ok(())
+    }
+}
+
+///Serializes macroquad's Color as [r, g, b, a]
+pub mod color {
+    use macroquad::prelude::Color;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(color: &Color, serializer: S) -> Result<S::Ok, S::Error> {
+        [color.r, color.g, color.b, color.a].serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+        let [r, g, b, a] = <[f32; 4]>::deserialize(deserializer)?;
+        Ok(Color { r, g, b, a })
+    }
+}
@@ -0,0 +1,165 @@
+use std::{
+    f64::consts::PI,
+    fs::File,
+    io::{self, BufWriter, Write},
+};
+
+use crate::{
+    meth::normalize_angle,
+    phase::{Coordinate, Quantity},
+    simulation::Simulation,
+};
+
+///Which way the surface has to be crossed to count
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Increasing,
+    Decreasing,
+    Both,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 3] = [
+        Direction::Increasing,
+        Direction::Decreasing,
+        Direction::Both,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Direction::Increasing => "increasing",
+            Direction::Decreasing => "decreasing",
+            Direction::Both => "both",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Sample {
+    surface: f64,
+    time: f64,
+    x: f64,
+    y: f64,
+}
+
+///Poincaré section, points of x and y whenever the surface coordinate crosses the level
+#[derive(Clone)]
+pub struct Section {
+    pub surface: Coordinate,
+    pub level: f64,
+    pub direction: Direction,
+    pub x: Coordinate,
+    pub y: Coordinate,
+
+    ///(time, x, y) of every crossing, angles wrapped into -pi..pi
+    pub points: Vec<(f64, f64, f64)>,
+    previous: Option<Sample>,
+}
+
+impl Section {
+    pub fn new(surface: Coordinate, x: Coordinate, y: Coordinate) -> Self {
+        Self {
+            surface,
+            level: 0.0,
+            direction: Direction::Increasing,
+            x,
+            y,
+            points: Vec::new(),
+            previous: None,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.points.clear();
+        self.previous = None;
+    }
+
+    ///Forgets the last sample so the jump to a new state is not taken as a crossing
+    pub fn interrupt(&mut self) {
+        self.previous = None;
+    }
+
+    //distance from the surface, angles are measured the short way around the circle
+    fn distance(&self, value: f64) -> f64 {
+        wrap(self.surface, value - self.level)
+    }
+
+    ///Checks the state after a step against the previous one, call after every step
+    pub fn observe(&mut self, simulation: &Simulation) {
+        let (Some(surface), Some(x), Some(y)) = (
+            self.surface.value(simulation),
+            self.x.value(simulation),
+            self.y.value(simulation),
+        ) else {
+            self.previous = None;
+            return;
+        };
+
+        let current = Sample {
+            surface: self.distance(surface),
+            time: simulation.time,
+            x,
+            y,
+        };
+
+        if let Some(previous) = self.previous.replace(current) {
+            let increasing = previous.surface < 0.0 && current.surface >= 0.0;
+            let decreasing = previous.surface > 0.0 && current.surface <= 0.0;
+            //wrapped angle going around through the opposite side is not a crossing
+            let wrapped = (current.surface - previous.surface).abs() > PI
+                && self.surface.quantity == Quantity::Angle;
+
+            let crossed = match self.direction {
+                Direction::Increasing => increasing,
+                Direction::Decreasing => decreasing,
+                Direction::Both => increasing || decreasing,
+            };
+
+            if crossed && !wrapped {
+                //linear interpolation to the exact crossing
+                let ratio = previous.surface / (previous.surface - current.surface);
+                let lerp = |a: f64, b: f64| a + (b - a) * ratio;
+
+                self.points.push((
+                    lerp(previous.time, current.time),
+                    wrap(self.x, lerp(previous.x, current.x)),
+                    wrap(self.y, lerp(previous.y, current.y)),
+                ));
+            }
+        }
+    }
+
+    pub fn plot_points(&self) -> Vec<[f64; 2]> {
+        self.points.iter().map(|&(_, x, y)| [x, y]).collect()
+    }
+
+    pub fn write_csv(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(
+            out,
+            "time,{}_{},{}_{}",
+            self.x.quantity.name(),
+            self.x.dulum + 1,
+            self.y.quantity.name(),
+            self.y.dulum + 1
+        )?;
+        for (time, x, y) in &self.points {
+            writeln!(out, "{time},{x},{y}")?;
+        }
+        Ok(())
+    }
+
+    pub fn save_csv(&self, path: &str) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        self.write_csv(&mut out)?;
+        out.flush()
+    }
+}
+
+///Angles into -pi..pi, other quantities stay
+pub fn wrap(coordinate: Coordinate, value: f64) -> f64 {
+    if coordinate.quantity == Quantity::Angle {
+        normalize_angle(value + PI) - PI
+    } else {
+        value
+    }
+}
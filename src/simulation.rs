@@ -0,0 +1,913 @@
+use std::fmt;
+
+use macroquad::prelude::*;
+use nalgebra::{DMatrix, DVector};
+
+use crate::{
+    collision::{contact_jacobian, find_contacts, separation_speeds, Collisions, REST_SPEED},
+    constraint::{
+        accumulate_constraint_rows, accumulate_violation, constrained_accelerations, correction,
+        Constraint, Endpoint, Stabilization,
+    },
+    dulum::Dulum,
+    gravity::GravityField,
+    integrator::{Integrator, IntegratorKind, State},
+    meth::solve,
+    pivot::{Kinematics, Pivot},
+    solver::SolverKind,
+    spring::{
+        accumulate_spring_dissipation, accumulate_spring_energy, accumulate_spring_forces, Spring,
+    },
+};
+
+pub const DULUMS_COLORS: [Color; 6] = [
+    color_u8!(255, 0, 0, 255),
+    color_u8!(255, 255, 0, 255),
+    color_u8!(0, 255, 0, 255),
+    color_u8!(0, 255, 255, 255),
+    color_u8!(0, 0, 255, 255),
+    color_u8!(255, 0, 255, 255),
+];
+
+///Index of the dulum every dulum hangs from, None for the pivot
+pub fn accumulate_parents(dulums: &[Dulum]) -> Vec<Option<usize>> {
+    dulums
+        .iter()
+        .enumerate()
+        .map(|(ind, x)| x.parent.index(ind))
+        .collect()
+}
+
+///For every dulum which bobs move along with it, itself included
+pub fn accumulate_subtrees(dulums: &[Dulum]) -> Vec<Vec<bool>> {
+    let count = dulums.len();
+    let parents = accumulate_parents(dulums);
+    let mut subtrees = vec![vec![false; count]; count];
+
+    //parents come first, so going backwards every subtree is done before its parent gets it
+    for ind in (0..count).rev() {
+        subtrees[ind][ind] = true;
+        if let Some(parent) = parents[ind] {
+            let (before, after) = subtrees.split_at_mut(ind);
+            for (moved, child) in before[parent].iter_mut().zip(&after[0]) {
+                *moved |= *child;
+            }
+        }
+    }
+
+    subtrees
+}
+
+///Walks from the pivot down the tree, every dulum gets what step makes of its parent's value,
+///dulums hanging from the pivot start from what root gives them
+pub fn walk_tree<T: Copy>(
+    dulums: &[Dulum],
+    root: impl Fn(&Dulum) -> T,
+    mut step: impl FnMut(&Dulum, T) -> T,
+) -> Vec<T> {
+    let mut values: Vec<T> = Vec::with_capacity(dulums.len());
+
+    for (ind, dulum) in dulums.iter().enumerate() {
+        let parent = match dulum.parent.index(ind) {
+            Some(parent) => values[parent],
+            None => root(dulum),
+        };
+        values.push(step(dulum, parent));
+    }
+
+    values
+}
+
+///Where a dulum hanging from the pivot is anchored
+pub fn anchor_position(dulum: &Dulum, pivot: &Kinematics) -> (f64, f64) {
+    (
+        pivot.position.0 + dulum.anchor.0,
+        pivot.position.1 + dulum.anchor.1,
+    )
+}
+
+pub fn accumulate_jacobi(dulums: &[Dulum]) -> DMatrix<f64> {
+    let subtrees = accumulate_subtrees(dulums);
+
+    let columns = dulums
+        .iter()
+        .zip(&subtrees)
+        .flat_map(|(x, moved)| {
+            let (j_vec_1, j_vec_2) = x.get_jacobi_vectors(moved);
+            [Some(j_vec_1), j_vec_2]
+        })
+        .flatten()
+        .collect::<Vec<_>>();
+
+    DMatrix::from_columns(columns.as_slice())
+}
+
+pub fn accumulate_constraint(dulums: &[Dulum]) -> DMatrix<f64> {
+    let subtrees = accumulate_subtrees(dulums);
+
+    let columns = dulums
+        .iter()
+        .zip(&subtrees)
+        .flat_map(|(x, moved)| {
+            let (j_vec_1, j_vec_2) = x.get_partial_constraint(moved);
+            [Some(j_vec_1), j_vec_2]
+        })
+        .flatten()
+        .collect::<Vec<_>>();
+
+    let partial_constraint = DMatrix::from_columns(columns.as_slice());
+
+    let elements: Vec<_> = dulums
+        .iter()
+        .flat_map(|x| {
+            let (a, b) = x.get_coordinates_der();
+            [Some(a), b]
+        })
+        .flatten()
+        .collect();
+
+    let derivatives = DMatrix::from_vec(elements.len(), 1, elements);
+
+    partial_constraint * derivatives
+}
+
+pub fn accumulate_mass(dulums: &[Dulum]) -> DMatrix<f64> {
+    DMatrix::from_diagonal(&DVector::from_iterator(
+        dulums.len() * 2,
+        dulums.iter().flat_map(|x| [x.mass, x.mass]),
+    ))
+}
+
+pub fn accumulate_hooks_force(dulums: &[Dulum]) -> DMatrix<f64> {
+    let elements: Vec<_> = dulums
+        .iter()
+        .flat_map(|x| {
+            let (a, b) = x.hooks_force();
+            [Some(a), b]
+        })
+        .flatten()
+        .collect();
+
+    DMatrix::from_vec(elements.len(), 1, elements)
+}
+
+pub fn accumulate_damping_force(dulums: &[Dulum]) -> DMatrix<f64> {
+    let mut elements = Vec::with_capacity(dulums.len() * 2);
+    //where the angle of every dulum is in the generalized coordinates
+    let mut angles: Vec<usize> = Vec::with_capacity(dulums.len());
+
+    for (dulum, parent) in dulums.iter().zip(accumulate_parents(dulums)) {
+        let parent_angle_der = parent.map_or(0.0, |parent| dulums[parent].angle_der);
+        let (a, b) = dulum.damping_force(parent_angle_der);
+        //friction pushes back on the parent dulum
+        if let Some(parent) = parent {
+            elements[angles[parent]] -= a;
+        }
+
+        angles.push(elements.len());
+        elements.push(a);
+        elements.extend(b);
+    }
+
+    DMatrix::from_vec(elements.len(), 1, elements)
+}
+
+pub fn accumulate_drag(dulums: &[Dulum], pivot: &Kinematics) -> DMatrix<f64> {
+    let elements: Vec<_> = dulums
+        .iter()
+        .zip(accumulate_velocities(dulums, pivot))
+        .flat_map(|(x, (vx, vy))| {
+            let (fx, fy) = x.drag_force(vx, vy);
+            [fx, fy]
+        })
+        .collect();
+
+    DMatrix::from_vec(elements.len(), 1, elements)
+}
+
+///Power the damping and drag take out of the dulums
+pub fn accumulate_dissipation(dulums: &[Dulum], springs: &[Spring], pivot: &Kinematics) -> f64 {
+    let drag: f64 = dulums
+        .iter()
+        .zip(accumulate_velocities(dulums, pivot))
+        .map(|(x, (vx, vy))| {
+            let (fx, fy) = x.drag_force(vx, vy);
+            fx * vx + fy * vy
+        })
+        .sum();
+
+    let state = accumulate_state(dulums, 0.0);
+    let damping = accumulate_damping_force(dulums)
+        .column(0)
+        .dot(&state.velocities);
+
+    accumulate_spring_dissipation(springs, dulums, pivot) - (drag + damping)
+}
+
+pub fn accumulate_positions(dulums: &[Dulum], pivot: &Kinematics) -> Vec<(f64, f64)> {
+    let root = |dulum: &Dulum| anchor_position(dulum, pivot);
+    walk_tree(dulums, root, |dulum, (x, y)| {
+        (
+            x + dulum.len * dulum.angle.sin(),
+            y + dulum.len * dulum.angle.cos(),
+        )
+    })
+}
+
+///Absolute velocities of the bobs
+pub fn accumulate_velocities(dulums: &[Dulum], pivot: &Kinematics) -> Vec<(f64, f64)> {
+    walk_tree(
+        dulums,
+        |_| pivot.velocity,
+        |dulum, (vx, vy)| {
+            let (_, vx, vy) = dulum.kinetic_energy(vx, vy);
+            (vx, vy)
+        },
+    )
+}
+
+pub fn accumulate_gravity(
+    dulums: &[Dulum],
+    gravity: &GravityField,
+    pivot: &Kinematics,
+) -> DMatrix<f64> {
+    let elements: Vec<_> = accumulate_positions(dulums, pivot)
+        .into_iter()
+        .flat_map(|(x, y)| {
+            let (ax, ay) = gravity.acceleration(x, y);
+            [ax, ay]
+        })
+        .collect();
+
+    DMatrix::from_vec(elements.len(), 1, elements)
+}
+
+///Every bob is carried along with the accelerating pivot
+pub fn accumulate_pivot_acceleration(dulums: &[Dulum], pivot: &Kinematics) -> DMatrix<f64> {
+    let (ax, ay) = pivot.acceleration;
+    let elements: Vec<_> = dulums.iter().flat_map(|_| [ax, ay]).collect();
+
+    DMatrix::from_vec(elements.len(), 1, elements)
+}
+
+//dulums shorter than this have no direction
+const MIN_LENGTH: f64 = 1e-9;
+
+///Why the dulums can't be simulated, dulums are indexed from zero
+#[derive(Clone, Debug, PartialEq)]
+pub enum SimulationError {
+    ZeroLength(usize),
+    NonPositiveMass(usize),
+    ///Something in the state went NaN or infinite
+    NotFinite,
+    ///The equations of motion have no solution
+    Singular,
+    ///Steps have to be positive, otherwise the time never moves on
+    InvalidTimeStep(f32),
+}
+
+impl fmt::Display for SimulationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SimulationError::ZeroLength(dulum) => write!(f, "dulum #{} has zero length", dulum + 1),
+            SimulationError::NonPositiveMass(dulum) => {
+                write!(f, "dulum #{} needs a positive mass", dulum + 1)
+            }
+            SimulationError::NotFinite => write!(f, "the state is not a finite number anymore"),
+            SimulationError::Singular => write!(f, "the equations of motion are singular"),
+            SimulationError::InvalidTimeStep(time_step) => {
+                write!(f, "time step {time_step} has to be a positive number")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SimulationError {}
+
+///Finds configurations the equations of motion can't handle
+pub fn check_dulums(dulums: &[Dulum]) -> Result<(), SimulationError> {
+    for (ind, dulum) in dulums.iter().enumerate() {
+        let numbers = [
+            dulum.angle,
+            dulum.angle_der,
+            dulum.len,
+            dulum.len_der,
+            dulum.mass,
+        ];
+        if !numbers.iter().all(|x| x.is_finite()) {
+            return Err(SimulationError::NotFinite);
+        }
+        if dulum.mass <= 0.0 {
+            return Err(SimulationError::NonPositiveMass(ind));
+        }
+        if dulum.len.abs() < MIN_LENGTH {
+            return Err(SimulationError::ZeroLength(ind));
+        }
+    }
+
+    Ok(())
+}
+
+//newton iterations of the projection onto the constraints and when they are good enough
+const PROJECTION_ITERATIONS: usize = 5;
+const PROJECTION_TOLERANCE: f64 = 1e-12;
+
+///Mass matrix of the generalized coordinates
+pub fn accumulate_generalized_mass(dulums: &[Dulum]) -> DMatrix<f64> {
+    let jacobi = accumulate_jacobi(dulums);
+    jacobi.transpose() * accumulate_mass(dulums) * jacobi
+}
+
+///Mass matrix and forces of the generalized coordinates, mass * accelerations = forces
+pub fn accumulate_system(
+    dulums: &[Dulum],
+    springs: &[Spring],
+    gravity: &GravityField,
+    pivot: &Kinematics,
+) -> (DMatrix<f64>, DVector<f64>) {
+    //gain variables
+    let jacobi = accumulate_jacobi(dulums);
+    let jacobi_trans = jacobi.transpose();
+    let mass = accumulate_mass(dulums);
+    let constraint = accumulate_constraint(dulums);
+    let hooks = accumulate_hooks_force(dulums);
+    let damping = accumulate_damping_force(dulums);
+    let drag = accumulate_drag(dulums, pivot) + accumulate_spring_forces(springs, dulums, pivot);
+    let gravity = accumulate_gravity(dulums, gravity, pivot);
+    let pivot = accumulate_pivot_acceleration(dulums, pivot);
+
+    //calculate sides
+    let left = jacobi_trans.clone() * mass.clone() * jacobi;
+    let right = hooks + damping + jacobi_trans * (drag + mass * (gravity - pivot - constraint));
+
+    (left, right.column(0).into_owned())
+}
+
+///Solves the equations of motion for the current state of the dulums,
+///returns accelerations of the generalized coordinates (angle, and len if elastic, per dulum)
+pub fn accumulate_accelerations(
+    dulums: &[Dulum],
+    springs: &[Spring],
+    gravity: &GravityField,
+    pivot: &Kinematics,
+) -> Result<DVector<f64>, SimulationError> {
+    check_dulums(dulums)?;
+
+    let (left, right) = accumulate_system(dulums, springs, gravity, pivot);
+    let right = DMatrix::from_column_slice(right.len(), 1, right.as_slice());
+    let accelerations = solve(&left, &right).ok_or(SimulationError::Singular)?;
+
+    finite(accelerations.column(0).into_owned())
+}
+
+fn finite(accelerations: DVector<f64>) -> Result<DVector<f64>, SimulationError> {
+    if accelerations.iter().all(|x| x.is_finite()) {
+        Ok(accelerations)
+    } else {
+        Err(SimulationError::NotFinite)
+    }
+}
+
+///Same as accumulate_accelerations with the extra constraints held by Lagrange multipliers,
+///the solver is only used without constraints as they need the whole mass matrix anyway
+pub fn accumulate_constrained_accelerations(
+    dulums: &[Dulum],
+    springs: &[Spring],
+    gravity: &GravityField,
+    pivot: &Kinematics,
+    constraints: &[Constraint],
+    stabilization: &Stabilization,
+    solver: SolverKind,
+) -> Result<DVector<f64>, SimulationError> {
+    if constraints.is_empty() {
+        return solver.accelerations(dulums, springs, gravity, pivot);
+    }
+    check_dulums(dulums)?;
+
+    let (mass, forces) = accumulate_system(dulums, springs, gravity, pivot);
+    let rows = accumulate_constraint_rows(constraints, dulums, pivot);
+    let accelerations = constrained_accelerations(&mass, &forces, &rows, stabilization)
+        .ok_or(SimulationError::Singular)?;
+
+    finite(accelerations)
+}
+
+pub fn accumulate_state(dulums: &[Dulum], time: f64) -> State {
+    let coordinates: Vec<_> = dulums
+        .iter()
+        .flat_map(|x| {
+            let (a, b) = x.get_coordinates();
+            [Some(a), b]
+        })
+        .flatten()
+        .collect();
+    let velocities: Vec<_> = dulums
+        .iter()
+        .flat_map(|x| {
+            let (a, b) = x.get_coordinates_der();
+            [Some(a), b]
+        })
+        .flatten()
+        .collect();
+
+    State {
+        time,
+        coordinates: DVector::from_vec(coordinates),
+        velocities: DVector::from_vec(velocities),
+    }
+}
+
+///Writes the generalized state back into the dulums
+pub fn distribute_state(dulums: &mut [Dulum], state: &State) {
+    let mut pointer = 0;
+    for dulum in dulums {
+        if dulum.is_elastic() {
+            dulum.set_coordinates(
+                state.coordinates[pointer],
+                Some(state.coordinates[pointer + 1]),
+            );
+            dulum.set_coordinates_der(
+                state.velocities[pointer],
+                Some(state.velocities[pointer + 1]),
+            );
+            pointer += 2;
+        } else {
+            dulum.set_coordinates(state.coordinates[pointer], None);
+            dulum.set_coordinates_der(state.velocities[pointer], None);
+            pointer += 1;
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Energy {
+    pub potential_gravity: f64,
+    pub potential_elastic: f64,
+    pub kinetic: f64,
+}
+
+impl Energy {
+    pub fn total(&self) -> f64 {
+        self.potential_gravity + self.potential_elastic + self.kinetic
+    }
+}
+
+#[derive(Clone)]
+pub struct Simulation {
+    pub dulums: Vec<Dulum>,
+    pub gravity: GravityField,
+    pub time: f64,
+    pub time_step: f32,
+    pub time_budget: f32,
+    pub integrator: Box<dyn Integrator>,
+    ///Energy taken out by friction, drag and damping since the start
+    pub dissipated: f64,
+    pub pivot: Pivot,
+    ///Rods and pins on top of the chain
+    pub constraints: Vec<Constraint>,
+    pub stabilization: Stabilization,
+    pub solver: SolverKind,
+    ///Springs between bobs or bobs and fixed points
+    pub springs: Vec<Spring>,
+    pub collisions: Collisions,
+}
+
+impl Simulation {
+    pub fn new(dulums: Vec<Dulum>) -> Self {
+        Self {
+            dulums,
+            gravity: GravityField::default(),
+            time: 0.0,
+            time_step: 0.001,
+            time_budget: 0.0,
+            integrator: IntegratorKind::Leapfrog.build(),
+            dissipated: 0.0,
+            pivot: Pivot::default(),
+            constraints: Vec::new(),
+            stabilization: Stabilization::default(),
+            solver: SolverKind::default(),
+            springs: Vec::new(),
+            collisions: Collisions::default(),
+        }
+    }
+
+    ///Adds dt to the time budget and spends it in steps of time_step,
+    ///adaptive integrators spend all of it in steps of their own choosing,
+    ///returns false if the budget snow balled and the simulation should stop
+    pub fn step(&mut self, dt: f32) -> Result<bool, SimulationError> {
+        self.step_observed(dt, |_| {})
+    }
+
+    ///Same as step, but the observer sees the simulation after every single integrator step
+    pub fn step_observed(
+        &mut self,
+        dt: f32,
+        mut observer: impl FnMut(&Simulation),
+    ) -> Result<bool, SimulationError> {
+        self.check_time_step()?;
+        //add to the time budget
+        self.time_budget += dt;
+        //snow balling protection
+        if self.time_budget >= 1.0 {
+            self.time_budget = 0.0;
+            return Ok(false);
+        }
+
+        if self.integrator.error_control().is_some() {
+            let budget = std::mem::take(&mut self.time_budget);
+            self.advance_observed(budget as f64, &mut observer)?;
+            return Ok(true);
+        }
+
+        while self.time_budget >= self.time_step {
+            self.time_budget -= self.time_step;
+            if let Err(err) = self.advance_observed(self.time_step as f64, &mut observer) {
+                self.time_budget = 0.0;
+                return Err(err);
+            }
+        }
+
+        Ok(true)
+    }
+
+    fn check_time_step(&self) -> Result<(), SimulationError> {
+        if self.time_step > 0.0 && self.time_step.is_finite() {
+            Ok(())
+        } else {
+            Err(SimulationError::InvalidTimeStep(self.time_step))
+        }
+    }
+
+    pub fn state(&self) -> State {
+        accumulate_state(&self.dulums, self.time)
+    }
+
+    pub fn set_state(&mut self, state: &State) {
+        self.time = state.time;
+        distribute_state(&mut self.dulums, state);
+    }
+
+    ///Advances the dulums by exactly one time_step
+    pub fn substep(&mut self) -> Result<(), SimulationError> {
+        self.advance(self.time_step as f64)
+    }
+
+    ///Advances the dulums by dt, adaptive integrators may split it into several steps
+    pub fn advance(&mut self, dt: f64) -> Result<(), SimulationError> {
+        self.advance_observed(dt, &mut |_| {})
+    }
+
+    ///Same as advance, calls the observer after every step of the integrator,
+    ///a step which fails is taken back
+    pub fn advance_observed(
+        &mut self,
+        dt: f64,
+        observer: &mut impl FnMut(&Simulation),
+    ) -> Result<(), SimulationError> {
+        let mut state = self.state();
+        let gravity = self.gravity;
+
+        let mut remaining = dt;
+        let mut power =
+            accumulate_dissipation(&self.dulums, &self.springs, &self.pivot_kinematics());
+        while remaining > 0.0 {
+            let dulums = &mut self.dulums;
+            let pivot = &self.pivot;
+            let (constraints, stabilization, solver, springs) = (
+                &self.constraints,
+                &self.stabilization,
+                self.solver,
+                &self.springs,
+            );
+            //integrators can't fail, so the first error is kept and the step thrown away
+            let mut failure = None;
+            let mut accelerations = |state: &State| {
+                distribute_state(dulums, state);
+                accumulate_constrained_accelerations(
+                    dulums,
+                    springs,
+                    &gravity,
+                    &pivot.kinematics(state.time),
+                    constraints,
+                    stabilization,
+                    solver,
+                )
+                .unwrap_or_else(|err| {
+                    failure.get_or_insert(err);
+                    DVector::zeros(state.velocities.len())
+                })
+            };
+
+            let before = state.clone();
+            let step = self
+                .integrator
+                .step(&mut state, remaining, &mut accelerations);
+            let failure = failure.or_else(|| {
+                let numbers = state.coordinates.iter().chain(state.velocities.iter());
+                (!numbers.copied().all(f64::is_finite)).then_some(SimulationError::NotFinite)
+            });
+            if let Some(err) = failure {
+                self.set_state(&before);
+                return Err(err);
+            }
+            remaining -= step;
+            self.pivot.advance(state.time);
+
+            //trapezoid rule for the dissipated energy
+            self.set_state(&state);
+            if self.collisions.is_active() {
+                self.resolve_collisions();
+                state = self.state();
+            }
+            if self.stabilization.projection && !self.constraints.is_empty() {
+                self.project_constraints();
+                state = self.state();
+            }
+            let new_power =
+                accumulate_dissipation(&self.dulums, &self.springs, &self.pivot_kinematics());
+            self.dissipated += (power + new_power) * step / 2.0;
+            power = new_power;
+
+            observer(self);
+        }
+
+        Ok(())
+    }
+
+    ///Advances by duration in steps of time_step, the last one shorter to land exactly,
+    ///the observer sees every step
+    pub fn run_for(
+        &mut self,
+        duration: f64,
+        observer: &mut impl FnMut(&Simulation),
+    ) -> Result<(), SimulationError> {
+        self.check_time_step()?;
+        let time_step = self.time_step as f64;
+        let mut remaining = duration;
+
+        while remaining > 0.0 {
+            let step = time_step.min(remaining);
+            remaining -= step;
+            self.advance_observed(step, observer)?;
+        }
+
+        Ok(())
+    }
+
+    ///Moves the dulums back onto the constraints, positions first, then velocities
+    pub fn project_constraints(&mut self) {
+        let pivot = self.pivot_kinematics();
+        let mass = accumulate_generalized_mass(&self.dulums);
+
+        for _ in 0..PROJECTION_ITERATIONS {
+            let rows = accumulate_constraint_rows(&self.constraints, &self.dulums, &pivot);
+            if rows.value.is_empty() || rows.value.amax() < PROJECTION_TOLERANCE {
+                break;
+            }
+            let Some(change) = correction(&mass, &rows.jacobian, &rows.value) else {
+                return;
+            };
+            let mut state = self.state();
+            state.coordinates += change;
+            distribute_state(&mut self.dulums, &state);
+        }
+
+        let mass = accumulate_generalized_mass(&self.dulums);
+        let rows = accumulate_constraint_rows(&self.constraints, &self.dulums, &pivot);
+        if let Some(change) = correction(&mass, &rows.jacobian, &rows.rate) {
+            let mut state = self.state();
+            state.velocities += change;
+            distribute_state(&mut self.dulums, &state);
+        }
+    }
+
+    ///Pushes overlapping bobs apart and bounces the ones moving into each other,
+    ///the bounce keeps the rods and pins, kinetic energy it takes out counts as dissipated
+    pub fn resolve_collisions(&mut self) {
+        let pivot = self.pivot_kinematics();
+        let contacts = find_contacts(&self.collisions, &self.dulums, &pivot);
+        if contacts.is_empty() {
+            return;
+        }
+
+        //positions first, the overlaps close
+        let mass = accumulate_generalized_mass(&self.dulums);
+        let jacobian = contact_jacobian(&contacts, &accumulate_jacobi(&self.dulums));
+        let gaps = DVector::from_iterator(contacts.len(), contacts.iter().map(|x| x.gap));
+        if let Some(change) = correction(&mass, &jacobian, &gaps) {
+            let mut state = self.state();
+            state.coordinates += change;
+            distribute_state(&mut self.dulums, &state);
+        }
+
+        //then only the contacts moving into each other bounce, slow ones just stop
+        let speeds = separation_speeds(&contacts, &self.velocities());
+        let (approaching, speeds): (Vec<_>, Vec<_>) = contacts
+            .into_iter()
+            .zip(speeds.iter().copied())
+            .filter(|(_, speed)| *speed < 0.0)
+            .unzip();
+        if !approaching.is_empty() {
+            let restitution = self.collisions.restitution;
+            let bounces = speeds.iter().filter(|x| -**x > REST_SPEED).count();
+            self.collisions.events += bounces;
+
+            //rods and pins keep their length while bouncing
+            let rows = accumulate_constraint_rows(&self.constraints, &self.dulums, &pivot);
+            let contact = contact_jacobian(&approaching, &accumulate_jacobi(&self.dulums));
+            let mut jacobian =
+                DMatrix::zeros(contact.nrows() + rows.jacobian.nrows(), contact.ncols());
+            jacobian.rows_mut(0, contact.nrows()).copy_from(&contact);
+            jacobian
+                .rows_mut(contact.nrows(), rows.jacobian.nrows())
+                .copy_from(&rows.jacobian);
+            let violation = DVector::from_iterator(
+                jacobian.nrows(),
+                speeds
+                    .iter()
+                    .map(|x| {
+                        if -x > REST_SPEED {
+                            (1.0 + restitution) * x
+                        } else {
+                            *x
+                        }
+                    })
+                    .chain(rows.rate.iter().copied()),
+            );
+
+            let mass = accumulate_generalized_mass(&self.dulums);
+            if let Some(change) = correction(&mass, &jacobian, &violation) {
+                //only the bounce loses energy, pushing apart only moves the bobs
+                let before = self.kinetic_energy();
+                let mut state = self.state();
+                state.velocities += change;
+                distribute_state(&mut self.dulums, &state);
+                self.dissipated += before - self.kinetic_energy();
+            }
+        }
+    }
+
+    ///Largest violation of the constraints in meters
+    pub fn constraint_violation(&self) -> f64 {
+        accumulate_violation(&self.constraints, &self.dulums, &self.pivot_kinematics())
+            .into_iter()
+            .fold(0.0, |max, x| max.max(x.abs()))
+    }
+
+    ///Starts the clock and dissipated energy from zero, used when the dulums get replaced
+    pub fn restart(&mut self) {
+        self.time = 0.0;
+        self.time_budget = 0.0;
+        self.dissipated = 0.0;
+        self.collisions.events = 0;
+        self.pivot.restart(0.0);
+    }
+
+    ///Swaps in a new chain, rods, pins and springs point at bobs of the old one and obstacles
+    ///were placed around it, so they all go, the clock starts from zero
+    pub fn replace_dulums(&mut self, dulums: Vec<Dulum>) {
+        self.dulums = dulums;
+        self.constraints.clear();
+        self.springs.clear();
+        self.collisions = Collisions::default();
+        self.restart();
+    }
+
+    pub fn pivot_kinematics(&self) -> Kinematics {
+        self.pivot.kinematics(self.time)
+    }
+
+    ///Positions of the bobs
+    pub fn positions(&self) -> Vec<(f64, f64)> {
+        accumulate_positions(&self.dulums, &self.pivot_kinematics())
+    }
+
+    ///Absolute velocities of the bobs
+    pub fn velocities(&self) -> Vec<(f64, f64)> {
+        accumulate_velocities(&self.dulums, &self.pivot_kinematics())
+    }
+
+    ///Position and velocity of what every dulum hangs from
+    pub fn parent_kinematics(&self) -> Vec<((f64, f64), (f64, f64))> {
+        let pivot = self.pivot_kinematics();
+        let (positions, velocities) = (self.positions(), self.velocities());
+
+        self.dulums
+            .iter()
+            .zip(accumulate_parents(&self.dulums))
+            .map(|(dulum, parent)| match parent {
+                Some(parent) => (positions[parent], velocities[parent]),
+                None => (anchor_position(dulum, &pivot), pivot.velocity),
+            })
+            .collect()
+    }
+
+    ///Energies of every dulum, kinetic energy uses the absolute velocity of the bob
+    pub fn energies(&self) -> Vec<Energy> {
+        self.dulums
+            .iter()
+            .zip(self.parent_kinematics())
+            .map(|(dulum, ((x, y), (vx, vy)))| {
+                let (potential_gravity, _, _) = dulum.potential_gravity_energy(x, y, &self.gravity);
+                let (kinetic, _, _) = dulum.kinetic_energy(vx, vy);
+
+                Energy {
+                    potential_gravity,
+                    potential_elastic: dulum.potential_elastic_energy(),
+                    kinetic,
+                }
+            })
+            .collect()
+    }
+
+    ///Energy stored in the springs between bobs, not part of any single dulum
+    pub fn spring_energy(&self) -> f64 {
+        accumulate_spring_energy(&self.springs, &self.dulums, &self.pivot_kinematics())
+    }
+
+    pub fn kinetic_energy(&self) -> f64 {
+        self.energies().iter().map(|x| x.kinetic).sum()
+    }
+
+    pub fn total_energy(&self) -> f64 {
+        self.energies().iter().map(Energy::total).sum::<f64>() + self.spring_energy()
+    }
+
+    ///Draws trails, lines and bobs of the chain, also records the trails
+    pub fn render(&mut self) {
+        //where every dulum starts
+        let positions = self.positions();
+        let starts: Vec<(f32, f32)> = self
+            .parent_kinematics()
+            .into_iter()
+            .map(|((x, y), _)| (x as f32, y as f32))
+            .collect();
+
+        //trails
+        for (dulum, (x, y)) in self.dulums.iter_mut().zip(&starts) {
+            dulum.add_trail(*x, *y);
+            dulum.render_trail();
+        }
+        //lines
+        for (dulum, (x, y)) in self.dulums.iter().zip(&starts) {
+            dulum.render_line(*x, *y);
+        }
+
+        //rods and pins
+        let position = |endpoint: Endpoint| match endpoint {
+            Endpoint::Bob(bob) => (positions[bob].0 as f32, positions[bob].1 as f32),
+            Endpoint::Fixed(x, y) => (x as f32, y as f32),
+        };
+        for spring in self.springs.iter().filter(|x| x.is_valid(positions.len())) {
+            render_spring(position(spring.a), position(spring.b));
+        }
+        for constraint in self
+            .constraints
+            .iter()
+            .filter(|x| x.is_valid(positions.len()))
+        {
+            match *constraint {
+                Constraint::Rod { a, b, .. } => {
+                    let (a, b) = (position(a), position(b));
+                    draw_line(a.0, a.1, b.0, b.1, 0.08, GRAY);
+                }
+                Constraint::Pin { position, .. } => {
+                    let (x, y) = (position.0 as f32, position.1 as f32);
+                    draw_rectangle_lines(x - 0.15, y - 0.15, 0.3, 0.3, 0.05, GRAY);
+                }
+            }
+        }
+
+        for obstacle in &self.collisions.obstacles {
+            obstacle.render();
+        }
+
+        //mass
+        for (dulum, (x, y)) in self.dulums.iter().zip(&starts) {
+            dulum.render_circle(*x, *y);
+        }
+    }
+}
+
+//zigzag between the ends with the same number of coils however long it is
+fn render_spring(a: (f32, f32), b: (f32, f32)) {
+    const COILS: usize = 8;
+    const WIDTH: f32 = 0.1;
+
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let len = dx.hypot(dy);
+    if len <= f32::EPSILON {
+        return;
+    }
+    let (nx, ny) = (-dy / len * WIDTH, dx / len * WIDTH);
+
+    let mut previous = a;
+    for i in 1..=COILS * 2 {
+        let along = i as f32 / (COILS * 2 + 1) as f32;
+        let side = if i % 2 == 0 { -1.0 } else { 1.0 };
+        let next = (a.0 + dx * along + nx * side, a.1 + dy * along + ny * side);
+        draw_line(previous.0, previous.1, next.0, next.1, 0.04, LIGHTGRAY);
+        previous = next;
+    }
+    draw_line(previous.0, previous.1, b.0, b.1, 0.04, LIGHTGRAY);
+}
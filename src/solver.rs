@@ -0,0 +1,201 @@
+use nalgebra::{DVector, Matrix2, Vector2};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    dulum::Dulum,
+    gravity::GravityField,
+    pivot::Kinematics,
+    simulation::{
+        accumulate_accelerations, accumulate_damping_force, accumulate_hooks_force,
+        accumulate_parents, accumulate_positions, accumulate_velocities, check_dulums,
+        SimulationError,
+    },
+    spring::{accumulate_spring_forces, Spring},
+};
+
+///How the equations of motion of the chain get solved
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SolverKind {
+    ///Builds the whole mass matrix and solves it, cubic in the number of dulums
+    #[default]
+    Dense,
+    ///Articulated body recursion along the chain, linear in the number of dulums
+    Recursive,
+}
+
+impl SolverKind {
+    pub const ALL: [SolverKind; 2] = [SolverKind::Dense, SolverKind::Recursive];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SolverKind::Dense => "Dense",
+            SolverKind::Recursive => "Recursive O(n)",
+        }
+    }
+
+    ///Accelerations of the generalized coordinates, same layout as accumulate_accelerations
+    pub fn accelerations(
+        &self,
+        dulums: &[Dulum],
+        springs: &[Spring],
+        gravity: &GravityField,
+        pivot: &Kinematics,
+    ) -> Result<DVector<f64>, SimulationError> {
+        match self {
+            SolverKind::Dense => accumulate_accelerations(dulums, springs, gravity, pivot),
+            SolverKind::Recursive => recursive_accelerations(dulums, springs, gravity, pivot),
+        }
+    }
+}
+
+//one link of the tree seen from its parent bob
+struct Link {
+    parent: Option<usize>,
+    //directions the bob moves in per unit of the angle and len velocity, second one zero if rigid
+    axes: Matrix2<f64>,
+    //acceleration of the bob from the velocities alone
+    bias: Vector2<f64>,
+    //generalized forces of the hooks law and damping
+    forces: Vector2<f64>,
+    elastic: bool,
+
+    //articulated inertia and bias force of everything from the bob down
+    inertia: Matrix2<f64>,
+    bias_force: Vector2<f64>,
+    //(axes^T inertia axes)^-1 over the dofs the link has
+    inverse: Matrix2<f64>,
+    //forces left over for the generalized accelerations
+    remaining: Vector2<f64>,
+}
+
+///Same accelerations as the dense solver without ever building the mass matrix,
+///every bob is a point mass so the articulated inertias are only 2x2,
+///branches of a tree add up in the bob they hang from
+pub fn recursive_accelerations(
+    dulums: &[Dulum],
+    springs: &[Spring],
+    gravity: &GravityField,
+    pivot: &Kinematics,
+) -> Result<DVector<f64>, SimulationError> {
+    check_dulums(dulums)?;
+
+    //hooks and damping come in the layout of the generalized coordinates
+    let generalized = accumulate_hooks_force(dulums) + accumulate_damping_force(dulums);
+    let pivot_acceleration = Vector2::new(pivot.acceleration.0, pivot.acceleration.1);
+
+    let positions = accumulate_positions(dulums, pivot);
+    let velocities = accumulate_velocities(dulums, pivot);
+    let spring_forces = accumulate_spring_forces(springs, dulums, pivot);
+    let mut pointer = 0;
+    let mut links = Vec::with_capacity(dulums.len());
+    //external forces on the bobs with the springs, pivot acceleration included as a fictitious force
+    let mut external = Vec::with_capacity(dulums.len());
+
+    for (ind, (((dulum, parent), (x, y)), (vx, vy))) in dulums
+        .iter()
+        .zip(accumulate_parents(dulums))
+        .zip(positions)
+        .zip(velocities)
+        .enumerate()
+    {
+        let (sin, cos) = dulum.angle.sin_cos();
+        let normal = Vector2::new(cos, -sin);
+        let along = Vector2::new(sin, cos);
+        let len_der = if dulum.elastic { dulum.len_der } else { 0.0 };
+
+        let (gx, gy) = gravity.acceleration(x, y);
+        let (fx, fy) = dulum.drag_force(vx, vy);
+        let (fx, fy) = (fx + spring_forces[ind * 2], fy + spring_forces[ind * 2 + 1]);
+        external
+            .push(Vector2::new(fx, fy) + (Vector2::new(gx, gy) - pivot_acceleration) * dulum.mass);
+
+        let mut forces = Vector2::new(generalized[pointer], 0.0);
+        pointer += 1;
+        if dulum.elastic {
+            forces.y = generalized[pointer];
+            pointer += 1;
+        }
+
+        links.push(Link {
+            parent,
+            axes: Matrix2::from_columns(&[
+                normal * dulum.len,
+                if dulum.elastic {
+                    along
+                } else {
+                    Vector2::zeros()
+                },
+            ]),
+            bias: normal * (2.0 * len_der * dulum.angle_der)
+                - along * (dulum.len * dulum.angle_der.powi(2)),
+            forces,
+            elastic: dulum.elastic,
+            inertia: Matrix2::zeros(),
+            bias_force: Vector2::zeros(),
+            inverse: Matrix2::zeros(),
+            remaining: Vector2::zeros(),
+        });
+    }
+
+    //from the last bob up, what the branches below pass on to the bob they hang from,
+    //children come after their parents so they are all done by the time the parent is
+    let mut carried_inertia = vec![Matrix2::zeros(); links.len()];
+    let mut carried_force = vec![Vector2::zeros(); links.len()];
+    for (ind, (link, dulum)) in links.iter_mut().zip(dulums).enumerate().rev() {
+        link.inertia = Matrix2::identity() * dulum.mass + carried_inertia[ind];
+        link.bias_force = carried_force[ind] - external[ind];
+
+        let joint = link.axes.transpose() * link.inertia * link.axes;
+        link.inverse = if link.elastic {
+            joint.try_inverse().ok_or(SimulationError::Singular)?
+        } else if joint.m11 > 0.0 {
+            Matrix2::new(1.0 / joint.m11, 0.0, 0.0, 0.0)
+        } else {
+            return Err(SimulationError::Singular);
+        };
+        link.remaining =
+            link.forces - link.axes.transpose() * (link.inertia * link.bias + link.bias_force);
+
+        //the pivot takes whatever the roots pass on
+        let Some(parent) = link.parent else {
+            continue;
+        };
+        //the joint takes the part of the inertia along its axes, the rest is
+        //I - I s (s^T I s)^-1 s^T I written so it stays positive for long chains,
+        //a rigid link passes on only the inertia along itself, an elastic one nothing
+        let transmitted = link.inertia * link.axes;
+        if !link.elastic {
+            let perpendicular = Vector2::new(-link.axes.m21, link.axes.m11);
+            carried_inertia[parent] += perpendicular
+                * perpendicular.transpose()
+                * (link.inertia.determinant() * link.inverse.m11);
+        }
+        carried_force[parent] += link.bias_force
+            + link.inertia * link.bias
+            + transmitted * link.inverse * link.remaining;
+    }
+
+    //from the pivot down, accelerations of the bobs relative to the pivot
+    let mut accelerations: Vec<Vector2<f64>> = Vec::with_capacity(links.len());
+    let mut result = Vec::with_capacity(pointer);
+    for link in &links {
+        let parent = link
+            .parent
+            .map_or(Vector2::zeros(), |parent| accelerations[parent]);
+        let generalized =
+            link.inverse * (link.remaining - link.axes.transpose() * link.inertia * parent);
+        accelerations.push(parent + link.axes * generalized + link.bias);
+
+        result.push(generalized.x);
+        if link.elastic {
+            result.push(generalized.y);
+        }
+    }
+
+    let result = DVector::from_vec(result);
+    if result.iter().all(|x| x.is_finite()) {
+        Ok(result)
+    } else {
+        Err(SimulationError::NotFinite)
+    }
+}
@@ -0,0 +1,142 @@
+use nalgebra::DMatrix;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    constraint::Endpoint,
+    dulum::Dulum,
+    meth::{hooks_energy, hooks_force, spring_damping_force},
+    pivot::Kinematics,
+    simulation::{accumulate_positions, accumulate_velocities},
+};
+
+//springs shorter than this have no direction
+const MIN_LENGTH: f64 = 1e-9;
+
+///Spring between two bobs or a bob and a fixed point, behaves like the spring of an elastic dulum
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Spring {
+    pub a: Endpoint,
+    pub b: Endpoint,
+    pub hardness: f64,
+    pub default_len: f64,
+    ///Goes slack instead of pushing when shorter than default_len
+    pub push_elastic: bool,
+    #[serde(default)]
+    pub damping: f64,
+}
+
+impl Spring {
+    pub fn new(a: Endpoint, b: Endpoint, hardness: f64, default_len: f64) -> Self {
+        Self {
+            a,
+            b,
+            hardness,
+            default_len,
+            push_elastic: false,
+            damping: 0.0,
+        }
+    }
+
+    ///Springs on bobs which don't exist are ignored
+    pub fn is_valid(&self, count: usize) -> bool {
+        self.a.is_valid(count) && self.b.is_valid(count) && self.a != self.b
+    }
+
+    ///Force pulling the ends together, same hooks law as elastic dulums,
+    ///rate is how fast the spring gets longer
+    pub fn tension(&self, len: f64, rate: f64) -> f64 {
+        -(hooks_force(self.hardness, self.default_len, self.push_elastic, len)
+            + spring_damping_force(self.damping, rate))
+    }
+
+    pub fn potential_energy(&self, len: f64) -> f64 {
+        hooks_energy(self.hardness, self.default_len, self.push_elastic, len)
+    }
+
+    ///Length, how fast it grows and the direction from b to a
+    fn stretch(
+        &self,
+        positions: &[(f64, f64)],
+        velocities: &[(f64, f64)],
+    ) -> (f64, f64, (f64, f64)) {
+        let end = |endpoint: Endpoint| match endpoint {
+            Endpoint::Bob(bob) => (positions[bob], velocities[bob]),
+            Endpoint::Fixed(x, y) => ((x, y), (0.0, 0.0)),
+        };
+        let ((a, va), (b, vb)) = (end(self.a), end(self.b));
+        let (dx, dy) = (a.0 - b.0, a.1 - b.1);
+        let len = dx.hypot(dy);
+
+        //ends on top of each other pull nowhere
+        if len < MIN_LENGTH {
+            return (len, 0.0, (0.0, 0.0));
+        }
+        let direction = (dx / len, dy / len);
+        let rate = (va.0 - vb.0) * direction.0 + (va.1 - vb.1) * direction.1;
+
+        (len, rate, direction)
+    }
+}
+
+///Forces of the springs on the bobs, same layout as accumulate_drag
+pub fn accumulate_spring_forces(
+    springs: &[Spring],
+    dulums: &[Dulum],
+    pivot: &Kinematics,
+) -> DMatrix<f64> {
+    let mut forces = DMatrix::zeros(dulums.len() * 2, 1);
+    if springs.is_empty() {
+        return forces;
+    }
+
+    let positions = accumulate_positions(dulums, pivot);
+    let velocities = accumulate_velocities(dulums, pivot);
+    for spring in springs.iter().filter(|x| x.is_valid(dulums.len())) {
+        let (len, rate, (dx, dy)) = spring.stretch(&positions, &velocities);
+        let tension = spring.tension(len, rate);
+
+        for (endpoint, sign) in [(spring.a, -1.0), (spring.b, 1.0)] {
+            if let Endpoint::Bob(bob) = endpoint {
+                forces[bob * 2] += sign * tension * dx;
+                forces[bob * 2 + 1] += sign * tension * dy;
+            }
+        }
+    }
+
+    forces
+}
+
+///Energy stored in all of the springs
+pub fn accumulate_spring_energy(springs: &[Spring], dulums: &[Dulum], pivot: &Kinematics) -> f64 {
+    let positions = accumulate_positions(dulums, pivot);
+    let velocities = accumulate_velocities(dulums, pivot);
+
+    springs
+        .iter()
+        .filter(|x| x.is_valid(dulums.len()))
+        .map(|x| x.potential_energy(x.stretch(&positions, &velocities).0))
+        .sum()
+}
+
+///Power the damping of the springs takes out
+pub fn accumulate_spring_dissipation(
+    springs: &[Spring],
+    dulums: &[Dulum],
+    pivot: &Kinematics,
+) -> f64 {
+    if springs.is_empty() {
+        return 0.0;
+    }
+
+    let positions = accumulate_positions(dulums, pivot);
+    let velocities = accumulate_velocities(dulums, pivot);
+
+    springs
+        .iter()
+        .filter(|x| x.is_valid(dulums.len()))
+        .map(|x| {
+            let rate = x.stretch(&positions, &velocities).1;
+            -spring_damping_force(x.damping, rate) * rate
+        })
+        .sum()
+}
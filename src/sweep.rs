@@ -0,0 +1,301 @@
+use std::{
+    fmt,
+    fs::File,
+    io::{self, BufWriter, Write},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use rayon::prelude::*;
+
+use crate::{
+    gravity::GravityField,
+    phase::Coordinate,
+    pivot::PivotMotion,
+    section::{self, Section},
+    simulation::Simulation,
+};
+
+///Samples for every value of the parameter
+pub type SweepResults = Vec<(f64, Vec<f64>)>;
+
+///What gets changed across the sweep, dulums are indexed from zero
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Parameter {
+    ///Amplitude of an oscillating pivot
+    DrivingAmplitude,
+    ///Strength of the gravity field
+    Gravity,
+    Mass(usize),
+    Hardness(usize),
+    DefaultLen(usize),
+}
+
+impl Parameter {
+    ///Every kind of parameter, the per dulum ones for the given dulum
+    pub fn all(dulum: usize) -> [Parameter; 5] {
+        [
+            Parameter::DrivingAmplitude,
+            Parameter::Gravity,
+            Parameter::Mass(dulum),
+            Parameter::Hardness(dulum),
+            Parameter::DefaultLen(dulum),
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Parameter::DrivingAmplitude => "driving amplitude",
+            Parameter::Gravity => "gravity",
+            Parameter::Mass(_) => "mass",
+            Parameter::Hardness(_) => "hardness",
+            Parameter::DefaultLen(_) => "default_len",
+        }
+    }
+
+    pub fn dulum(&self) -> Option<usize> {
+        match self {
+            Parameter::Mass(dulum) | Parameter::Hardness(dulum) | Parameter::DefaultLen(dulum) => {
+                Some(*dulum)
+            }
+            _ => None,
+        }
+    }
+
+    ///Same kind of parameter on another dulum
+    pub fn with_dulum(&self, dulum: usize) -> Parameter {
+        match self {
+            Parameter::Mass(_) => Parameter::Mass(dulum),
+            Parameter::Hardness(_) => Parameter::Hardness(dulum),
+            Parameter::DefaultLen(_) => Parameter::DefaultLen(dulum),
+            other => *other,
+        }
+    }
+
+    ///Command line name, e.g. `amplitude`, `gravity` or `mass_2`
+    pub fn key(&self) -> String {
+        match (self, self.dulum()) {
+            (Parameter::DrivingAmplitude, _) => "amplitude".to_string(),
+            (_, Some(dulum)) => format!("{}_{}", self.name(), dulum + 1),
+            _ => self.name().to_string(),
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Parameter> {
+        match key {
+            "amplitude" => return Some(Parameter::DrivingAmplitude),
+            "gravity" => return Some(Parameter::Gravity),
+            _ => {}
+        }
+
+        let (name, number) = key.rsplit_once('_')?;
+        let dulum = number.parse::<usize>().ok()?.checked_sub(1)?;
+        Parameter::all(dulum)
+            .into_iter()
+            .find(|parameter| parameter.dulum().is_some() && parameter.name() == name)
+    }
+
+    ///Sets the parameter, returns false if the simulation has nothing to set
+    pub fn apply(&self, simulation: &mut Simulation, value: f64) -> bool {
+        match self {
+            Parameter::DrivingAmplitude => match &mut simulation.pivot.motion {
+                PivotMotion::Vertical { amplitude, .. }
+                | PivotMotion::Horizontal { amplitude, .. } => {
+                    *amplitude = value;
+                }
+                _ => return false,
+            },
+            Parameter::Gravity => match &mut simulation.gravity {
+                GravityField::Uniform { magnitude, .. }
+                | GravityField::Gradient { magnitude, .. } => {
+                    *magnitude = value;
+                }
+                GravityField::PointMass { strength, .. } => *strength = value,
+            },
+            Parameter::Mass(dulum) | Parameter::Hardness(dulum) | Parameter::DefaultLen(dulum) => {
+                let Some(dulum) = simulation.dulums.get_mut(*dulum) else {
+                    return false;
+                };
+                match self {
+                    Parameter::Mass(_) => dulum.mass = value,
+                    Parameter::Hardness(_) => dulum.hardness = value,
+                    _ => dulum.default_len = value,
+                }
+            }
+        }
+
+        true
+    }
+}
+
+///Why a sweep can't run
+#[derive(Clone, Debug, PartialEq)]
+pub enum SweepError {
+    ///The simulation has nothing the parameter could change
+    NothingToSet(Parameter),
+}
+
+impl fmt::Display for SweepError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SweepError::NothingToSet(Parameter::DrivingAmplitude) => {
+                write!(
+                    f,
+                    "the pivot has to oscillate vertically or horizontally to sweep its amplitude"
+                )
+            }
+            SweepError::NothingToSet(parameter) => match parameter.dulum() {
+                Some(dulum) => write!(
+                    f,
+                    "there is no dulum #{} to sweep its {}",
+                    dulum + 1,
+                    parameter.name()
+                ),
+                None => write!(f, "the simulation has no {} to sweep", parameter.name()),
+            },
+        }
+    }
+}
+
+impl std::error::Error for SweepError {}
+
+///When the chosen coordinate gets sampled after the transient
+#[derive(Clone)]
+pub enum Sampling {
+    ///Whenever the section surface is crossed, the section's own x and y are ignored
+    Section(Section),
+    ///Every period, usually the period of the driving
+    Stroboscopic { period: f64 },
+}
+
+///Runs the same simulation for many values of one parameter and samples one coordinate,
+///the points make a bifurcation diagram
+#[derive(Clone)]
+pub struct Sweep {
+    pub base: Simulation,
+    pub parameter: Parameter,
+    pub from: f64,
+    pub to: f64,
+    pub count: usize,
+    ///Time thrown away before sampling
+    pub transient: f64,
+    ///Time of sampling after the transient
+    pub duration: f64,
+    pub coordinate: Coordinate,
+    pub sampling: Sampling,
+}
+
+impl Sweep {
+    pub fn values(&self) -> Vec<f64> {
+        if self.count <= 1 {
+            return vec![self.from];
+        }
+
+        (0..self.count)
+            .map(|i| self.from + (self.to - self.from) * i as f64 / (self.count - 1) as f64)
+            .collect()
+    }
+
+    ///Makes sure the parameter can be set, otherwise every value would run the same simulation
+    pub fn check(&self) -> Result<(), SweepError> {
+        let mut simulation = self.base.clone();
+        if self.parameter.apply(&mut simulation, self.from) {
+            Ok(())
+        } else {
+            Err(SweepError::NothingToSet(self.parameter))
+        }
+    }
+
+    ///Samples of the coordinate for one value of the parameter, a simulation which fails
+    ///during the transient gives none, one failing later keeps the samples it got until then
+    pub fn run_value(&self, value: f64) -> Vec<f64> {
+        let mut simulation = self.base.clone();
+        simulation.restart();
+        self.parameter.apply(&mut simulation, value);
+
+        if simulation.run_for(self.transient, &mut |_| {}).is_err() {
+            return Vec::new();
+        }
+
+        match &self.sampling {
+            Sampling::Section(section) => {
+                //surface crossings carry the sampled coordinate in x
+                let mut section = section.clone();
+                section.x = self.coordinate;
+                section.y = self.coordinate;
+                section.clear();
+                let _ = simulation
+                    .run_for(self.duration, &mut |simulation| section.observe(simulation));
+                section.points.iter().map(|&(_, x, _)| x).collect()
+            }
+            Sampling::Stroboscopic { period } => {
+                let mut samples = Vec::new();
+                let mut elapsed = 0.0;
+                while elapsed + period <= self.duration && *period > 0.0 {
+                    if simulation.run_for(*period, &mut |_| {}).is_err() {
+                        break;
+                    }
+                    elapsed += period;
+                    samples.extend(
+                        self.coordinate
+                            .value(&simulation)
+                            .map(|value| section::wrap(self.coordinate, value)),
+                    );
+                }
+                samples
+            }
+        }
+    }
+
+    ///All values in parallel, done counts finished values as they come
+    pub fn run(&self, done: &AtomicUsize) -> Result<SweepResults, SweepError> {
+        self.check()?;
+
+        Ok(self
+            .values()
+            .into_par_iter()
+            .map(|value| {
+                let samples = self.run_value(value);
+                done.fetch_add(1, Ordering::Relaxed);
+                (value, samples)
+            })
+            .collect())
+    }
+}
+
+///Flattens sweep results into points of the diagram
+pub fn diagram_points(results: &[(f64, Vec<f64>)]) -> Vec<[f64; 2]> {
+    results
+        .iter()
+        .flat_map(|(value, samples)| samples.iter().map(move |sample| [*value, *sample]))
+        .collect()
+}
+
+pub fn write_csv(
+    out: &mut impl Write,
+    parameter: Parameter,
+    coordinate: Coordinate,
+    results: &[(f64, Vec<f64>)],
+) -> io::Result<()> {
+    writeln!(
+        out,
+        "{},{}_{}",
+        parameter.key(),
+        coordinate.quantity.name(),
+        coordinate.dulum + 1
+    )?;
+    for [value, sample] in diagram_points(results) {
+        writeln!(out, "{value},{sample}")?;
+    }
+    Ok(())
+}
+
+pub fn save_csv(
+    path: &str,
+    parameter: Parameter,
+    coordinate: Coordinate,
+    results: &[(f64, Vec<f64>)],
+) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    write_csv(&mut out, parameter, coordinate, results)?;
+    out.flush()
+}
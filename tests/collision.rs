@@ -0,0 +1,116 @@
+use fyr_pendulum_too::{
+    collision::{find_contacts, Obstacle},
+    dulum::Parent,
+    integrator::IntegratorKind,
+    meth::deg2rad,
+    simulation::Simulation,
+};
+
+use common::{assert_close, dulum};
+
+mod common;
+
+#[test]
+fn obstacle_contacts() {
+    let (gap, normal) = Obstacle::Floor { y: 2.0 }.contact((0.5, 1.9), 0.2);
+    assert_close(gap, -0.1, 1e-12);
+    assert_eq!(normal, (0.0, -1.0));
+
+    let (gap, normal) = Obstacle::Wall { x: -1.0 }.contact((-0.5, 0.0), 0.2);
+    assert_close(gap, 0.3, 1e-12);
+    assert_eq!(normal, (1.0, 0.0));
+
+    let circle = Obstacle::Circle {
+        center: (1.0, 1.0),
+        radius: 0.5,
+    };
+    let (gap, normal) = circle.contact((1.0, 1.6), 0.2);
+    assert_close(gap, -0.1, 1e-12);
+    assert_close(normal.0, 0.0, 1e-12);
+    assert_close(normal.1, 1.0, 1e-12);
+}
+
+#[test]
+fn bounce_off_wall_keeps_restitution() {
+    //swings from the left into a wall right of the lowest point
+    let mut simulation = Simulation::new(vec![dulum(deg2rad(-60.0), 0.0, 1.0, 1.0)]);
+    simulation
+        .collisions
+        .obstacles
+        .push(Obstacle::Wall { x: 0.3 });
+    simulation.collisions.restitution = 0.5;
+    simulation.integrator = IntegratorKind::RungeKutta4.build();
+
+    let (mut before, mut after) = (0.0, None);
+    simulation
+        .run_for(1.0, &mut |simulation| {
+            let angle_der = simulation.dulums[0].angle_der;
+            if simulation.collisions.events == 0 {
+                before = angle_der;
+            } else {
+                after.get_or_insert(angle_der);
+            }
+        })
+        .unwrap();
+
+    assert_eq!(simulation.collisions.events, 1);
+    let after = after.unwrap();
+    assert!(before > 0.0 && after < 0.0);
+    assert_close(-after / before, 0.5, 0.02);
+    //the bounce takes out 1 - e^2 of the kinetic energy, len and mass are 1
+    let kinetic = before.powi(2) / 2.0;
+    assert_close(
+        simulation.dissipated,
+        kinetic * (1.0 - 0.5f64.powi(2)),
+        0.02 * kinetic,
+    );
+}
+
+#[test]
+fn newtons_cradle_passes_the_swing_on() {
+    //two equal pendulums just touching, the left one comes in from the side
+    let mut right = dulum(0.0, 0.0, 1.0, 1.0);
+    right.parent = Parent::Pivot;
+    right.anchor = (0.4, 0.0);
+    let mut simulation = Simulation::new(vec![dulum(deg2rad(-40.0), 0.0, 1.0, 1.0), right]);
+    simulation.collisions.bobs = true;
+    simulation.collisions.restitution = 1.0;
+    simulation.integrator = IntegratorKind::RungeKutta4.build();
+
+    let start = simulation.total_energy();
+    let mut incoming = 0.0;
+    let mut bounced = false;
+    while !bounced {
+        incoming = simulation.dulums[0].angle_der;
+        simulation.substep().unwrap();
+        bounced = simulation.collisions.events > 0;
+    }
+
+    assert_close(simulation.dulums[0].angle_der, 0.0, 0.02 * incoming);
+    assert_close(simulation.dulums[1].angle_der, incoming, 0.02 * incoming);
+    assert_eq!(simulation.collisions.events, 1);
+    assert_close(simulation.total_energy(), start, 1e-2);
+    //a perfectly elastic bounce takes nothing out
+    assert_close(simulation.dissipated, 0.0, 1e-9);
+}
+
+#[test]
+fn siblings_dont_collide_at_their_joint() {
+    //two dulums hanging from the same bob overlap right away
+    let mut first = dulum(0.1, 0.0, 0.3, 1.0);
+    first.parent = Parent::Dulum(0);
+    let mut second = dulum(0.2, 0.0, 0.3, 1.0);
+    second.parent = Parent::Dulum(0);
+    let mut simulation = Simulation::new(vec![dulum(0.0, 0.0, 1.0, 1.0), first, second]);
+    simulation.collisions.bobs = true;
+
+    assert!(find_contacts(
+        &simulation.collisions,
+        &simulation.dulums,
+        &simulation.pivot_kinematics()
+    )
+    .is_empty());
+    simulation.run_for(0.5, &mut |_| {}).unwrap();
+    assert_eq!(simulation.collisions.events, 0);
+    assert_eq!(simulation.dissipated, 0.0);
+}
@@ -0,0 +1,23 @@
+//not every test file uses all of these
+#![allow(dead_code)]
+
+use fyr_pendulum_too::dulum::Dulum;
+use macroquad::prelude::WHITE;
+
+pub fn dulum(angle: f64, angle_der: f64, len: f64, mass: f64) -> Dulum {
+    let mut dulum = Dulum::new(angle, len, mass, false, 100.0, len, WHITE, 0.2);
+    dulum.angle_der = angle_der;
+    dulum
+}
+
+pub fn elastic(angle: f64, angle_der: f64, len: f64, len_der: f64, mass: f64) -> Dulum {
+    let mut dulum = dulum(angle, angle_der, len, mass);
+    dulum.elastic = true;
+    dulum.len_der = len_der;
+    dulum.default_len = 1.0;
+    dulum
+}
+
+pub fn assert_close(a: f64, b: f64, tolerance: f64) {
+    assert!((a - b).abs() <= tolerance, "{a} is not close to {b}");
+}
@@ -0,0 +1,68 @@
+use fyr_pendulum_too::{
+    constraint::{Constraint, Endpoint, Stabilization},
+    simulation::Simulation,
+};
+
+use common::dulum;
+
+mod common;
+
+//three dulums, the last one tied to the world by the constraint made from its position
+fn closed_chain(constraint: impl Fn((f64, f64)) -> Constraint) -> Simulation {
+    let mut simulation = Simulation::new(vec![
+        dulum(1.0, 0.0, 1.0, 1.0),
+        dulum(-0.4, 0.0, 0.8, 0.7),
+        dulum(0.6, 0.0, 0.6, 0.5),
+    ]);
+    let last = simulation.positions()[2];
+    simulation.constraints.push(constraint(last));
+    simulation
+}
+
+//largest violation seen during the run
+fn worst_violation(simulation: &mut Simulation, duration: f64) -> f64 {
+    let mut worst: f64 = 0.0;
+    simulation
+        .run_for(duration, &mut |simulation| {
+            worst = worst.max(simulation.constraint_violation())
+        })
+        .unwrap();
+    worst
+}
+
+#[test]
+fn stabilized_constraints_dont_drift() {
+    let rod = |(x, y): (f64, f64)| Constraint::Rod {
+        a: Endpoint::Bob(2),
+        b: Endpoint::Fixed(x + 0.5, y),
+        length: 0.5,
+    };
+    let pin = |position| Constraint::Pin { bob: 2, position };
+
+    for (name, mut simulation) in [("rod", closed_chain(rod)), ("pin", closed_chain(pin))] {
+        assert!(simulation.constraint_violation() < 1e-12);
+
+        let worst = worst_violation(&mut simulation, 8.0);
+        assert!(worst < 1e-6, "{name} drifted by {worst}");
+    }
+}
+
+#[test]
+fn baumgarte_alone_keeps_the_rod_bounded() {
+    let rod = |(x, y): (f64, f64)| Constraint::Rod {
+        a: Endpoint::Bob(2),
+        b: Endpoint::Fixed(x + 0.5, y),
+        length: 0.5,
+    };
+    let mut simulation = closed_chain(rod);
+    simulation.stabilization = Stabilization {
+        projection: false,
+        ..Stabilization::default()
+    };
+
+    let early = worst_violation(&mut simulation, 4.0);
+    let late = worst_violation(&mut simulation, 4.0);
+    //without projection it wobbles a little, but the feedback doesn't let it grow
+    assert!(early < 1e-2, "drifted by {early}");
+    assert!(late <= early, "grew from {early} to {late}");
+}
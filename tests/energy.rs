@@ -0,0 +1,210 @@
+use fyr_pendulum_too::{
+    dulum::{Dulum, Parent},
+    integrator::IntegratorKind,
+    simulation::{accumulate_jacobi, accumulate_mass, Simulation},
+};
+use nalgebra::DVector;
+
+use common::{assert_close, dulum, elastic};
+
+mod common;
+
+const GRAVITY: f64 = 9.8;
+
+#[test]
+fn single_pendulum_matches_analytic_energy() {
+    let (angle, angle_der, len, mass) = (0.7, -1.3, 1.5, 2.0);
+    let simulation = Simulation::new(vec![dulum(angle, angle_der, len, mass)]);
+
+    let kinetic = mass * len.powi(2) * angle_der.powi(2) / 2.0;
+    let potential = -mass * GRAVITY * len * angle.cos();
+
+    let energies = simulation.energies();
+    assert_close(energies[0].kinetic, kinetic, 1e-12);
+    assert_close(energies[0].potential_gravity, potential, 1e-12);
+    assert_close(simulation.total_energy(), kinetic + potential, 1e-12);
+}
+
+#[test]
+fn double_pendulum_matches_analytic_energy() {
+    let (a1, w1, l1, m1) = (0.4, 2.1, 1.0, 1.5);
+    let (a2, w2, l2, m2) = (-1.2, -0.6, 2.0, 0.5);
+    let simulation = Simulation::new(vec![dulum(a1, w1, l1, m1), dulum(a2, w2, l2, m2)]);
+
+    let kinetic = (m1 + m2) * l1.powi(2) * w1.powi(2) / 2.0
+        + m2 * l2.powi(2) * w2.powi(2) / 2.0
+        + m2 * l1 * l2 * w1 * w2 * (a1 - a2).cos();
+    let potential = -(m1 + m2) * GRAVITY * l1 * a1.cos() - m2 * GRAVITY * l2 * a2.cos();
+
+    let energies = simulation.energies();
+    assert_close(energies[0].kinetic + energies[1].kinetic, kinetic, 1e-12);
+    assert_close(simulation.total_energy(), kinetic + potential, 1e-12);
+}
+
+#[test]
+fn kinetic_energy_agrees_with_jacobian() {
+    let mut elastic = dulum(-0.3, 0.8, 1.2, 0.7);
+    elastic.elastic = true;
+    elastic.len_der = -0.9;
+
+    let dulums = vec![
+        dulum(1.1, -0.4, 1.0, 1.0),
+        elastic,
+        dulum(2.5, 1.7, 0.5, 2.0),
+    ];
+    let simulation = Simulation::new(dulums.clone());
+
+    let velocities = DVector::from_iterator(
+        4,
+        dulums
+            .iter()
+            .flat_map(|x| {
+                let (a, b) = x.get_coordinates_der();
+                [Some(a), b]
+            })
+            .flatten(),
+    );
+    let cartesian = accumulate_jacobi(&dulums) * velocities;
+    let kinetic = (cartesian.transpose() * accumulate_mass(&dulums) * cartesian)[0] / 2.0;
+
+    let energies = simulation.energies();
+    assert_close(energies.iter().map(|x| x.kinetic).sum(), kinetic, 1e-12);
+}
+
+#[test]
+fn double_pendulum_conserves_energy() {
+    let mut simulation = Simulation::new(vec![
+        dulum(std::f64::consts::FRAC_PI_2, 0.0, 1.0, 1.0),
+        dulum(std::f64::consts::FRAC_PI_2, 0.0, 1.0, 1.0),
+    ]);
+    simulation.time_step = 0.0005;
+
+    let start = simulation.total_energy();
+    for _ in 0..4000 {
+        simulation.substep().unwrap();
+    }
+
+    assert_close(simulation.total_energy(), start, 0.05);
+}
+
+//two pendulums hang from the first bob, a third one straight from the pivot
+fn fork() -> Vec<Dulum> {
+    let mut elastic = dulum(-0.3, 0.8, 1.2, 0.7);
+    elastic.elastic = true;
+    elastic.len_der = -0.9;
+    elastic.parent = Parent::Dulum(0);
+
+    let mut free = dulum(2.5, 1.7, 0.5, 2.0);
+    free.parent = Parent::Pivot;
+
+    vec![
+        dulum(1.1, -0.4, 1.0, 1.0),
+        dulum(0.6, 1.3, 0.9, 1.5),
+        elastic,
+        free,
+    ]
+}
+
+#[test]
+fn tree_kinetic_energy_agrees_with_jacobian() {
+    let dulums = fork();
+    let simulation = Simulation::new(dulums.clone());
+
+    let velocities = simulation.state().velocities;
+    let cartesian = accumulate_jacobi(&dulums) * velocities;
+    let kinetic = (cartesian.transpose() * accumulate_mass(&dulums) * cartesian)[0] / 2.0;
+
+    let energies = simulation.energies();
+    assert_close(energies.iter().map(|x| x.kinetic).sum(), kinetic, 1e-12);
+}
+
+#[test]
+fn fork_matches_analytic_energy() {
+    let (a1, w1, l1, m1) = (0.4, 2.1, 1.0, 1.5);
+    let (a2, w2, l2, m2) = (-1.2, -0.6, 2.0, 0.5);
+    let (a3, w3, l3, m3) = (0.9, 1.4, 0.5, 0.8);
+    let mut third = dulum(a3, w3, l3, m3);
+    third.parent = Parent::Dulum(0);
+    let simulation = Simulation::new(vec![dulum(a1, w1, l1, m1), dulum(a2, w2, l2, m2), third]);
+
+    //both branches ride on the first bob
+    let kinetic = (m1 + m2 + m3) * l1.powi(2) * w1.powi(2) / 2.0
+        + m2 * l2.powi(2) * w2.powi(2) / 2.0
+        + m2 * l1 * l2 * w1 * w2 * (a1 - a2).cos()
+        + m3 * l3.powi(2) * w3.powi(2) / 2.0
+        + m3 * l1 * l3 * w1 * w3 * (a1 - a3).cos();
+    let potential = -(m1 + m2 + m3) * GRAVITY * l1 * a1.cos()
+        - m2 * GRAVITY * l2 * a2.cos()
+        - m3 * GRAVITY * l3 * a3.cos();
+
+    assert_close(simulation.total_energy(), kinetic + potential, 1e-12);
+}
+
+#[test]
+fn tree_conserves_energy() {
+    let mut simulation = Simulation::new(fork());
+    simulation.integrator = IntegratorKind::RungeKutta4.build();
+    simulation.time_step = 0.0005;
+
+    let start = simulation.total_energy();
+    simulation.run_for(2.0, &mut |_| {}).unwrap();
+
+    assert_close(simulation.total_energy(), start, 1e-3);
+}
+
+#[test]
+fn anchored_chains_move_alike() {
+    //the same double pendulum twice, the second copy hangs from the pivot 3 m to the right
+    let mut chain = vec![dulum(1.0, 0.0, 1.0, 1.0), dulum(-0.5, 0.3, 0.7, 0.5)];
+    let mut copy = chain.clone();
+    copy[0].parent = Parent::Pivot;
+    copy[0].anchor = (3.0, 0.0);
+    copy[1].parent = Parent::Dulum(2);
+    chain.extend(copy);
+
+    let mut simulation = Simulation::new(chain);
+    simulation.integrator = IntegratorKind::RungeKutta4.build();
+    simulation.run_for(1.0, &mut |_| {}).unwrap();
+
+    let positions = simulation.positions();
+    for i in 0..2 {
+        assert_close(positions[i + 2].0 - positions[i].0, 3.0, 1e-9);
+        assert_close(positions[i + 2].1, positions[i].1, 1e-9);
+    }
+}
+
+#[test]
+fn damping_and_drag_are_booked_as_dissipated() {
+    let mut first = dulum(1.2, 0.5, 1.0, 1.0);
+    first.joint_friction = 0.3;
+    first.linear_drag = 0.2;
+    let mut second = elastic(-0.6, 1.5, 1.1, 0.2, 0.8);
+    second.spring_damping = 1.5;
+    second.quadratic_drag = 0.4;
+
+    let mut simulation = Simulation::new(vec![first, second]);
+    simulation.integrator = IntegratorKind::RungeKutta4.build();
+    simulation.time_step = 0.001;
+
+    let start = simulation.total_energy();
+    for _ in 0..6 {
+        for _ in 0..500 {
+            simulation.substep().unwrap();
+        }
+
+        //whatever the dulums lost went to the dissipated energy
+        let energies = simulation.energies();
+        let kinetic: f64 = energies.iter().map(|x| x.kinetic).sum();
+        let potential: f64 = energies
+            .iter()
+            .map(|x| x.potential_gravity + x.potential_elastic)
+            .sum();
+        assert_close(kinetic + potential + simulation.dissipated, start, 1e-4);
+    }
+
+    assert!(
+        simulation.dissipated > 1.0,
+        "only {} dissipated",
+        simulation.dissipated
+    );
+}
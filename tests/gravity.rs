@@ -0,0 +1,37 @@
+use fyr_pendulum_too::gravity::GravityField;
+
+use common::assert_close;
+
+mod common;
+
+#[test]
+fn acceleration_is_minus_gradient_of_potential() {
+    let fields = [
+        GravityField::Uniform {
+            magnitude: 9.8,
+            direction: 0.4,
+        },
+        GravityField::PointMass {
+            position: (0.5, 3.0),
+            strength: 25.0,
+        },
+        GravityField::Gradient {
+            magnitude: 9.8,
+            direction: -0.7,
+            gradient: 1.3,
+        },
+    ];
+    //central differences
+    const H: f64 = 1e-5;
+
+    for field in fields {
+        for (x, y) in [(0.0, 0.0), (1.2, -0.8), (-2.5, 1.7), (0.3, 4.1)] {
+            let (ax, ay) = field.acceleration(x, y);
+            let dx = (field.potential(x + H, y) - field.potential(x - H, y)) / (2.0 * H);
+            let dy = (field.potential(x, y + H) - field.potential(x, y - H)) / (2.0 * H);
+
+            assert_close(ax, -dx, 1e-6);
+            assert_close(ay, -dy, 1e-6);
+        }
+    }
+}
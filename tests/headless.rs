@@ -0,0 +1,59 @@
+use std::{fs, process::Command};
+
+use fyr_pendulum_too::{
+    integrator::IntegratorKind, palette::Palette, scene::Scene, simulation::Simulation,
+};
+use macroquad::prelude::vec2;
+
+use common::dulum;
+
+mod common;
+
+//saves a chaotic double pendulum on dopri5 with the tolerances, returns the path
+fn save_scene(name: &str, tolerance: f64) -> String {
+    let mut simulation =
+        Simulation::new(vec![dulum(2.0, 0.0, 1.0, 1.0), dulum(2.5, 0.0, 1.0, 1.0)]);
+    simulation.time_step = 0.01;
+    simulation.integrator = IntegratorKind::DormandPrince.build();
+    let control = simulation.integrator.error_control().unwrap();
+    control.absolute_tolerance = tolerance;
+    control.relative_tolerance = tolerance;
+
+    let path = std::env::temp_dir().join(format!("fyr_pendulum_headless_{name}.ron"));
+    Scene::capture(&simulation, Palette::default(), 1.0, vec2(0.0, 0.0))
+        .save(&path)
+        .unwrap();
+    path.display().to_string()
+}
+
+fn headless(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_headless"))
+        .args(args)
+        .args(["--steps", "500", "--every", "100"])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn scene_tolerances_are_used() {
+    let loose = save_scene("loose", 1e-3);
+    let tight = save_scene("tight", 1e-10);
+
+    let from_loose = headless(&["--scene", &loose]);
+    //the tolerances are all the scenes differ in
+    assert_ne!(from_loose, headless(&["--scene", &tight]));
+    //options still win over the scene
+    assert_eq!(
+        from_loose,
+        headless(&["--scene", &tight, "--atol", "1e-3", "--rtol", "1e-3"])
+    );
+
+    fs::remove_file(loose).unwrap();
+    fs::remove_file(tight).unwrap();
+}
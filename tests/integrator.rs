@@ -0,0 +1,154 @@
+use fyr_pendulum_too::integrator::{IntegratorKind, State, DP_MIN_STEP};
+use nalgebra::DVector;
+
+const GRAVITY: f64 = 9.8;
+
+//simple pendulum of length 1, angle 1 rad from the bottom and at rest
+fn start() -> State {
+    State {
+        time: 0.0,
+        coordinates: DVector::from_element(1, 1.0),
+        velocities: DVector::zeros(1),
+    }
+}
+
+fn pendulum(state: &State) -> DVector<f64> {
+    state.coordinates.map(|angle| -GRAVITY * angle.sin())
+}
+
+fn energy(state: &State) -> f64 {
+    state.velocities[0].powi(2) / 2.0 - GRAVITY * state.coordinates[0].cos()
+}
+
+fn integrate(kind: IntegratorKind, dt: f64, duration: f64) -> State {
+    let mut integrator = kind.build();
+    let mut state = start();
+    for _ in 0..(duration / dt).round() as usize {
+        integrator.step(&mut state, dt, &mut pendulum);
+    }
+    state
+}
+
+#[test]
+fn fixed_step_integrators_converge_with_their_order() {
+    let reference = integrate(IntegratorKind::RungeKutta4, 1e-4, 1.0);
+
+    for (kind, order) in [
+        (IntegratorKind::Euler, 1.0),
+        (IntegratorKind::SemiImplicitEuler, 1.0),
+        (IntegratorKind::Leapfrog, 2.0),
+        (IntegratorKind::VelocityVerlet, 2.0),
+        (IntegratorKind::RungeKutta4, 4.0),
+        (IntegratorKind::Yoshida4, 4.0),
+    ] {
+        let error = |dt| (integrate(kind, dt, 1.0).coordinates - &reference.coordinates).norm();
+        //halving the step should cut the error by 2^order
+        let measured = (error(0.02) / error(0.01)).log2();
+        assert!(
+            (measured - order).abs() < 0.3,
+            "{} converges with order {measured}, expected {order}",
+            kind.name()
+        );
+    }
+}
+
+#[test]
+fn symplectic_integrators_dont_drift() {
+    let initial = energy(&start());
+
+    for (kind, bound) in [
+        (IntegratorKind::SemiImplicitEuler, 1e-1),
+        (IntegratorKind::Leapfrog, 2e-3),
+        (IntegratorKind::VelocityVerlet, 2e-3),
+        (IntegratorKind::Yoshida4, 1e-6),
+    ] {
+        //energy of symplectic ones oscillates, but stays near the start even after many periods
+        let mut integrator = kind.build();
+        let mut state = start();
+        let mut worst: f64 = 0.0;
+        for _ in 0..100_000 {
+            integrator.step(&mut state, 0.01, &mut pendulum);
+            worst = worst.max((energy(&state) - initial).abs());
+        }
+        assert!(worst < bound, "{} drifted by {worst}", kind.name());
+    }
+
+    //explicit euler gains energy every step
+    let euler = integrate(IntegratorKind::Euler, 0.01, 100.0);
+    assert!(energy(&euler) - initial > 1.0);
+}
+
+//runs an adaptive integrator until it covers the whole duration
+fn integrate_adaptive(tolerance: f64, duration: f64) -> State {
+    let mut integrator = IntegratorKind::DormandPrince.build();
+    let control = integrator.error_control().unwrap();
+    control.absolute_tolerance = tolerance;
+    control.relative_tolerance = tolerance;
+
+    let mut state = start();
+    let mut remaining = duration;
+    while remaining > 0.0 {
+        remaining -= integrator.step(&mut state, remaining, &mut pendulum);
+    }
+    state
+}
+
+#[test]
+fn dormand_prince_accepts_and_rejects_steps() {
+    let mut integrator = IntegratorKind::DormandPrince.build();
+    let mut state = start();
+
+    //smooth motion, every step is accepted and they grow up to the budget
+    let mut last = 0.0;
+    for _ in 0..50 {
+        let taken = integrator.step(&mut state, 0.5, &mut pendulum);
+        assert!(taken > 0.0 && taken <= 0.5);
+        assert_eq!(integrator.error_control().unwrap().step_size, taken);
+        last = taken;
+    }
+    assert_eq!(integrator.error_control().unwrap().rejected, 0);
+    assert!(last > 1e-2, "steps didn't grow, last one was {last}");
+
+    //a sudden kick makes the long steps miss the tolerance
+    let kick = state.time + last / 2.0;
+    let mut kicked = |state: &State| {
+        let mut acc = pendulum(state);
+        if state.time > kick {
+            acc[0] += 1e3;
+        }
+        acc
+    };
+    let before = state.time;
+    let taken = integrator.step(&mut state, 0.5, &mut kicked);
+    assert!(integrator.error_control().unwrap().rejected > 0);
+    assert_eq!(state.time, before + taken);
+}
+
+#[test]
+fn dormand_prince_takes_the_smallest_step_when_nothing_fits() {
+    let mut integrator = IntegratorKind::DormandPrince.build();
+    let mut state = start();
+
+    //so fast no step can follow it, it has to give up at the floor instead of looping forever
+    let mut wild = |state: &State| DVector::from_element(1, (state.time * 1e9).sin() * 1e12);
+    let taken = integrator.step(&mut state, 0.5, &mut wild);
+
+    assert_eq!(taken, DP_MIN_STEP);
+    assert!(integrator.error_control().unwrap().rejected > 0);
+}
+
+#[test]
+fn tighter_tolerance_is_more_accurate() {
+    let reference = integrate(IntegratorKind::RungeKutta4, 1e-4, 2.0);
+
+    let errors: Vec<f64> = [1e-4, 1e-7, 1e-10]
+        .into_iter()
+        .map(|tolerance| {
+            (integrate_adaptive(tolerance, 2.0).coordinates - &reference.coordinates).norm()
+        })
+        .collect();
+
+    assert!(errors[0] < 1e-2, "{errors:?}");
+    assert!(errors[1] < errors[0] / 10.0, "{errors:?}");
+    assert!(errors[2] < errors[1] / 10.0, "{errors:?}");
+}
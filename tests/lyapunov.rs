@@ -0,0 +1,59 @@
+use fyr_pendulum_too::{
+    dulum::Dulum,
+    lyapunov::Lyapunov,
+    pivot::{Pivot, PivotMotion},
+    simulation::Simulation,
+};
+
+use common::dulum;
+
+mod common;
+
+#[test]
+fn driven_pivot_keeps_its_phase() {
+    let mut simulation = Simulation::new(vec![dulum(0.5, 0.0, 1.0, 1.0)]);
+    simulation.pivot = Pivot::new(PivotMotion::Vertical {
+        amplitude: 0.2,
+        frequency: 1.3,
+    });
+    simulation.run_for(0.77, &mut |_| {}).unwrap();
+
+    let mut lyapunov = Lyapunov::new(&simulation, 1e-8, 0.1);
+    assert_eq!(lyapunov.reference.time, simulation.time);
+    assert_eq!(
+        lyapunov.reference.pivot_kinematics(),
+        simulation.pivot_kinematics()
+    );
+
+    //the reference is the chain on screen, just further along
+    lyapunov.advance(0.5).unwrap();
+    simulation.run_for(0.5, &mut |_| {}).unwrap();
+    assert!((lyapunov.reference.time - simulation.time).abs() < 1e-9);
+    assert!((lyapunov.reference.dulums[0].angle - simulation.dulums[0].angle).abs() < 1e-9);
+    assert!((lyapunov.elapsed() - 0.5).abs() < 1e-9);
+    assert!(lyapunov
+        .history
+        .first()
+        .is_some_and(|[time, _]| *time < 0.5));
+}
+
+//estimate after running for the duration
+fn exponent(dulums: Vec<Dulum>, duration: f64) -> f64 {
+    let mut lyapunov = Lyapunov::new(&Simulation::new(dulums), 1e-8, 0.1);
+    lyapunov.advance(duration).unwrap();
+    lyapunov.history.last().unwrap()[1]
+}
+
+#[test]
+fn regular_motion_has_zero_exponent_and_chaos_a_positive_one() {
+    //small swings of a single pendulum are as regular as it gets
+    let regular = exponent(vec![dulum(0.1, 0.0, 1.0, 1.0)], 20.0);
+    //double pendulum let go from high up flips around chaotically
+    let chaotic = exponent(
+        vec![dulum(2.0, 0.0, 1.0, 1.0), dulum(2.5, 0.0, 1.0, 1.0)],
+        20.0,
+    );
+
+    assert!(regular.abs() < 0.1, "regular motion has exponent {regular}");
+    assert!(chaotic > 1.0, "chaotic motion has exponent {chaotic}");
+}
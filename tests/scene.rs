@@ -0,0 +1,139 @@
+use std::fs;
+
+use fyr_pendulum_too::{
+    collision::Obstacle,
+    constraint::{Constraint, Endpoint},
+    expression::Expression,
+    gravity::GravityField,
+    integrator::IntegratorKind,
+    palette::Palette,
+    pivot::{Pivot, PivotMotion},
+    scene::Scene,
+    simulation::Simulation,
+    solver::SolverKind,
+    spring::Spring,
+};
+use macroquad::prelude::{vec2, Color};
+
+use common::{dulum, elastic};
+
+mod common;
+
+//setup with a bit of everything a scene holds
+fn simulation() -> Simulation {
+    let mut simulation = Simulation::new(vec![
+        dulum(0.4, -0.2, 1.0, 1.5),
+        elastic(-0.7, 0.3, 1.2, 0.1, 0.8),
+        dulum(1.1, 0.0, 0.6, 0.4),
+    ]);
+    simulation.gravity = GravityField::PointMass {
+        position: (0.5, 4.0),
+        strength: 30.0,
+    };
+    simulation.time_step = 0.002;
+    simulation.integrator = IntegratorKind::DormandPrince.build();
+    let control = simulation.integrator.error_control().unwrap();
+    control.absolute_tolerance = 1e-5;
+    control.relative_tolerance = 3e-6;
+    simulation.solver = SolverKind::Recursive;
+    simulation.pivot = Pivot::new(PivotMotion::Expression {
+        x: Expression::parse("0.2 * sin(3 * t)").unwrap(),
+        y: Expression::parse("0").unwrap(),
+    });
+    simulation.constraints = vec![Constraint::Pin {
+        bob: 2,
+        position: (0.3, 2.0),
+    }];
+    simulation.stabilization.projection = true;
+    simulation.springs = vec![Spring::new(
+        Endpoint::Bob(0),
+        Endpoint::Fixed(-1.0, 0.5),
+        20.0,
+        0.7,
+    )];
+    simulation.collisions.bobs = true;
+    simulation.collisions.obstacles = vec![Obstacle::Floor { y: 2.5 }];
+    simulation
+}
+
+fn round_trip(extension: &str) {
+    let palette = Palette::Gradient {
+        start: Color::new(0.1, 0.2, 0.3, 1.0),
+        end: Color::new(0.9, 0.8, 0.7, 0.5),
+    };
+    let original = simulation();
+    let path = std::env::temp_dir().join(format!("fyr_pendulum_round_trip.{extension}"));
+
+    Scene::capture(&original, palette, 42.0, vec2(1.0, -2.0))
+        .save(&path)
+        .unwrap();
+    let saved = fs::read_to_string(&path).unwrap();
+    let scene = Scene::load(&path).unwrap();
+    let mut restored = scene.to_simulation();
+
+    assert_eq!(scene.palette, palette);
+    assert_eq!(scene.camera_scale, 42.0);
+    assert_eq!(scene.camera_origin(), vec2(1.0, -2.0));
+
+    let control = restored.integrator.error_control().unwrap();
+    assert_eq!(control.absolute_tolerance, 1e-5);
+    assert_eq!(control.relative_tolerance, 3e-6);
+    assert_eq!(restored.integrator.kind(), IntegratorKind::DormandPrince);
+    assert_eq!(restored.solver, SolverKind::Recursive);
+    assert_eq!(restored.gravity, original.gravity);
+    assert_eq!(restored.constraints, original.constraints);
+    assert_eq!(restored.stabilization, original.stabilization);
+    assert_eq!(restored.springs, original.springs);
+    assert_eq!(restored.collisions, original.collisions);
+    assert_eq!(restored.state().coordinates, original.state().coordinates);
+    assert_eq!(restored.state().velocities, original.state().velocities);
+
+    //saving the loaded scene again gives the same file
+    Scene::capture(
+        &restored,
+        scene.palette,
+        scene.camera_scale,
+        scene.camera_origin(),
+    )
+    .save(&path)
+    .unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), saved);
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn ron_round_trip() {
+    round_trip("ron");
+}
+
+#[test]
+fn json_round_trip() {
+    round_trip("json");
+}
+
+#[test]
+fn old_scenes_keep_defaults() {
+    let path = std::env::temp_dir().join("fyr_pendulum_old_scene.ron");
+    let simulation = Simulation::new(vec![dulum(0.3, 0.0, 1.0, 1.0)]);
+    Scene::capture(&simulation, Palette::Rainbow, 1.0, vec2(0.0, 0.0))
+        .save(&path)
+        .unwrap();
+
+    //scene from before the tolerances and the palette were saved
+    let content = fs::read_to_string(&path).unwrap();
+    let content: Vec<&str> = content
+        .lines()
+        .filter(|line| {
+            !["tolerances", "palette"]
+                .iter()
+                .any(|x| line.trim_start().starts_with(x))
+        })
+        .collect();
+    fs::write(&path, content.join("\n")).unwrap();
+
+    let scene = Scene::load(&path).unwrap();
+    assert_eq!(scene.tolerances, None);
+    assert_eq!(scene.palette, Palette::default());
+    fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,88 @@
+use std::f64::consts::PI;
+
+use fyr_pendulum_too::{
+    phase::{Coordinate, Quantity},
+    section::{Direction, Section},
+    simulation::Simulation,
+};
+
+use common::{assert_close, dulum};
+
+mod common;
+
+//feeds the section states of a single dulum as (time, angle, angle_der)
+fn observe(section: &mut Section, states: &[(f64, f64, f64)]) {
+    let mut simulation = Simulation::new(vec![dulum(0.0, 0.0, 1.0, 1.0)]);
+    for &(time, angle, angle_der) in states {
+        simulation.time = time;
+        simulation.dulums[0].angle = angle;
+        simulation.dulums[0].angle_der = angle_der;
+        section.observe(&simulation);
+    }
+}
+
+fn angle_section() -> Section {
+    Section::new(
+        Coordinate::new(0, Quantity::Angle),
+        Coordinate::new(0, Quantity::Angle),
+        Coordinate::new(0, Quantity::AngleDer),
+    )
+}
+
+#[test]
+fn crossing_is_interpolated() {
+    let mut section = angle_section();
+    //the angle goes from -0.1 to 0.3, so it hits zero a quarter of the way
+    observe(&mut section, &[(1.0, -0.1, 2.0), (1.4, 0.3, 6.0)]);
+
+    assert_eq!(section.points.len(), 1);
+    let (time, x, y) = section.points[0];
+    assert_close(time, 1.1, 1e-12);
+    assert_close(x, 0.0, 1e-12);
+    assert_close(y, 3.0, 1e-12);
+}
+
+#[test]
+fn direction_filters_crossings() {
+    let swing = [(0.0, -0.2, 1.0), (0.1, 0.2, 1.0), (0.2, -0.2, -1.0)];
+
+    for (direction, count) in [
+        (Direction::Increasing, 1),
+        (Direction::Decreasing, 1),
+        (Direction::Both, 2),
+    ] {
+        let mut section = angle_section();
+        section.direction = direction;
+        observe(&mut section, &swing);
+        assert_eq!(section.points.len(), count, "{}", direction.name());
+    }
+}
+
+#[test]
+fn angles_wrap_around() {
+    //after a full turn the angle crosses zero again, the point is wrapped back near zero
+    let mut section = angle_section();
+    observe(
+        &mut section,
+        &[(0.0, 2.0 * PI - 0.1, 1.0), (0.2, 2.0 * PI + 0.1, 1.0)],
+    );
+    assert_eq!(section.points.len(), 1);
+    let (time, x, _) = section.points[0];
+    assert_close(time, 0.1, 1e-12);
+    assert_close(x, 0.0, 1e-9);
+
+    //going over the top is not a crossing of the bottom
+    let mut section = angle_section();
+    observe(&mut section, &[(0.0, PI - 0.1, 1.0), (0.2, PI + 0.1, 1.0)]);
+    assert!(section.points.is_empty());
+
+    //but it is one of a level at the top, the x is wrapped into -pi..pi
+    let mut section = angle_section();
+    section.level = PI;
+    observe(&mut section, &[(0.0, PI - 0.1, 1.0), (0.2, PI + 0.3, 1.0)]);
+    assert_eq!(section.points.len(), 1);
+    let (time, x, _) = section.points[0];
+    assert_close(time, 0.05, 1e-12);
+    assert_close(x.abs(), PI, 1e-9);
+    assert!((-PI..=PI).contains(&x));
+}
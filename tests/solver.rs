@@ -0,0 +1,142 @@
+use std::f64::consts::PI;
+
+use fyr_pendulum_too::{
+    dulum::{Dulum, Parent},
+    gravity::GravityField,
+    integrator::IntegratorKind,
+    pivot::{Kinematics, PivotMotion},
+    simulation::{accumulate_accelerations, Simulation},
+    solver::{recursive_accelerations, SolverKind},
+};
+
+use common::{dulum, elastic};
+
+mod common;
+
+//deterministic chain with a bit of everything, every third dulum elastic
+fn chain(count: usize) -> Vec<Dulum> {
+    (0..count)
+        .map(|i| {
+            let x = i as f64;
+            let angle = (x * 1.7).sin() * PI;
+            let angle_der = (x * 0.9).cos() * 3.0;
+            let len = 0.5 + (x * 0.37).sin().abs();
+            let mass = 0.3 + (x * 1.3).cos().abs() * 2.0;
+            let mut dulum = if i % 3 == 1 {
+                elastic(angle, angle_der, len, (x * 2.1).sin(), mass)
+            } else {
+                dulum(angle, angle_der, len, mass)
+            };
+            dulum.joint_friction = 0.1 * (i % 2) as f64;
+            dulum.linear_drag = 0.05;
+            dulum.quadratic_drag = 0.02 * (i % 4) as f64;
+            dulum.spring_damping = 0.3;
+            dulum.push_elastic = i % 6 == 4;
+            dulum
+        })
+        .collect()
+}
+
+fn assert_agree(dulums: &[Dulum], gravity: &GravityField, pivot: &Kinematics) {
+    let dense = accumulate_accelerations(dulums, &[], gravity, pivot).unwrap();
+    let recursive = recursive_accelerations(dulums, &[], gravity, pivot).unwrap();
+
+    assert_eq!(dense.len(), recursive.len());
+    let scale = dense.amax().max(1.0);
+    for (i, (a, b)) in dense.iter().zip(recursive.iter()).enumerate() {
+        assert!(
+            (a - b).abs() <= 1e-9 * scale,
+            "coordinate {i}: dense {a}, recursive {b}"
+        );
+    }
+}
+
+#[test]
+fn single_pendulum_agrees() {
+    let dulums = vec![dulum(0.8, -1.5, 1.3, 2.0)];
+    assert_agree(&dulums, &GravityField::default(), &Kinematics::default());
+}
+
+#[test]
+fn rigid_and_elastic_chains_agree() {
+    for count in [2, 3, 6, 20, 120] {
+        assert_agree(
+            &chain(count),
+            &GravityField::default(),
+            &Kinematics::default(),
+        );
+    }
+
+    let springs = vec![
+        elastic(0.3, 1.0, 1.2, -0.4, 1.0),
+        elastic(-2.0, 0.5, 0.8, 0.7, 0.5),
+    ];
+    assert_agree(&springs, &GravityField::default(), &Kinematics::default());
+}
+
+#[test]
+fn trees_agree() {
+    //every dulum hangs from one a few places up, some straight from the pivot
+    let mut dulums = chain(30);
+    for (ind, dulum) in dulums.iter_mut().enumerate() {
+        dulum.parent = match ind % 7 {
+            0 => Parent::Pivot,
+            step => Parent::Dulum(ind.saturating_sub(1 + step % 3)),
+        };
+    }
+    assert_agree(&dulums, &GravityField::default(), &Kinematics::default());
+
+    //two pendulums hanging from the same bob
+    let mut fork = vec![
+        dulum(0.5, 1.0, 1.0, 1.0),
+        dulum(-0.3, 0.0, 0.8, 0.5),
+        elastic(1.2, -2.0, 1.1, 0.3, 0.7),
+    ];
+    fork[2].parent = Parent::Dulum(0);
+    assert_agree(&fork, &GravityField::default(), &Kinematics::default());
+}
+
+#[test]
+fn moving_pivot_and_field_agree() {
+    let pivot = Kinematics {
+        position: (0.3, -0.2),
+        velocity: (1.1, -0.4),
+        acceleration: (-2.5, 3.0),
+    };
+    let gravity = GravityField::PointMass {
+        position: (1.0, 4.0),
+        strength: 30.0,
+    };
+    assert_agree(&chain(8), &gravity, &pivot);
+}
+
+#[test]
+fn trajectories_agree() {
+    let mut dense = Simulation::new(chain(5));
+    dense.integrator = IntegratorKind::RungeKutta4.build();
+    dense.time_step = 0.001;
+    dense.pivot.motion = PivotMotion::Vertical {
+        amplitude: 0.1,
+        frequency: 2.0,
+    };
+    let mut recursive = dense.clone();
+    recursive.solver = SolverKind::Recursive;
+
+    dense.run_for(0.5, &mut |_| {}).unwrap();
+    recursive.run_for(0.5, &mut |_| {}).unwrap();
+
+    let difference = (dense.state().coordinates - recursive.state().coordinates).amax();
+    assert!(difference < 1e-8, "states differ by {difference}");
+}
+
+#[test]
+fn long_chain_stays_finite() {
+    let mut simulation = Simulation::new((0..300).map(|_| dulum(0.5, 0.0, 0.05, 0.01)).collect());
+    simulation.solver = SolverKind::Recursive;
+    simulation.integrator = IntegratorKind::RungeKutta4.build();
+    simulation.time_step = 0.0005;
+
+    let start = simulation.total_energy();
+    simulation.run_for(0.1, &mut |_| {}).unwrap();
+    assert!((simulation.total_energy() - start).abs() < 1e-3 * start.abs().max(1.0));
+}
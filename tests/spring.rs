@@ -0,0 +1,122 @@
+use fyr_pendulum_too::{
+    constraint::Endpoint,
+    dulum::{Dulum, Parent},
+    gravity::GravityField,
+    integrator::IntegratorKind,
+    pivot::Kinematics,
+    simulation::{accumulate_accelerations, Simulation},
+    solver::recursive_accelerations,
+    spring::Spring,
+};
+
+use common::{assert_close, dulum, elastic};
+
+mod common;
+
+fn stiff(mut dulum: Dulum, hardness: f64) -> Dulum {
+    dulum.hardness = hardness;
+    dulum
+}
+
+#[test]
+fn spring_to_the_pivot_matches_elastic_dulum() {
+    //a spring from the pivot to the bob of a soft elastic dulum is its own spring
+    let spring = |push_elastic| {
+        let mut spring = Spring::new(Endpoint::Bob(0), Endpoint::Fixed(0.0, 0.0), 40.0, 1.0);
+        spring.damping = 0.7;
+        spring.push_elastic = push_elastic;
+        spring
+    };
+    let (gravity, pivot) = (GravityField::default(), Kinematics::default());
+
+    for (len, push_elastic) in [(1.4, false), (0.8, false), (0.8, true)] {
+        let mut own = stiff(elastic(0.6, -1.1, len, 0.4, 1.0), 40.0);
+        own.spring_damping = 0.7;
+        own.push_elastic = push_elastic;
+        let expected = accumulate_accelerations(&[own], &[], &gravity, &pivot).unwrap();
+
+        let free = [stiff(elastic(0.6, -1.1, len, 0.4, 1.0), 0.0)];
+        let springs = [spring(push_elastic)];
+        for accelerations in [
+            accumulate_accelerations(&free, &springs, &gravity, &pivot).unwrap(),
+            recursive_accelerations(&free, &springs, &gravity, &pivot).unwrap(),
+        ] {
+            assert_close(accelerations[0], expected[0], 1e-12);
+            assert_close(accelerations[1], expected[1], 1e-12);
+        }
+    }
+}
+
+#[test]
+fn solvers_agree_with_springs() {
+    let dulums = vec![
+        dulum(0.8, -1.5, 1.3, 2.0),
+        stiff(elastic(-0.4, 0.7, 1.1, 0.3, 1.0), 50.0),
+        dulum(1.9, 0.2, 0.7, 0.5),
+        dulum(-2.5, 1.0, 1.0, 1.5),
+    ];
+    let springs = [
+        Spring::new(Endpoint::Bob(0), Endpoint::Bob(2), 30.0, 0.5),
+        Spring::new(Endpoint::Bob(3), Endpoint::Fixed(1.0, 2.0), 10.0, 1.0),
+        Spring {
+            damping: 0.4,
+            push_elastic: true,
+            ..Spring::new(Endpoint::Bob(1), Endpoint::Bob(3), 20.0, 5.0)
+        },
+    ];
+    let (gravity, pivot) = (GravityField::default(), Kinematics::default());
+
+    let dense = accumulate_accelerations(&dulums, &springs, &gravity, &pivot).unwrap();
+    let recursive = recursive_accelerations(&dulums, &springs, &gravity, &pivot).unwrap();
+    for (a, b) in dense.iter().zip(recursive.iter()) {
+        assert_close(*a, *b, 1e-9 * dense.amax().max(1.0));
+    }
+}
+
+#[test]
+fn coupled_pendulums_conserve_energy() {
+    //two pendulums side by side joined by a spring, only one starts swinging
+    let mut right = dulum(0.0, 0.0, 1.0, 1.0);
+    right.parent = Parent::Pivot;
+    right.anchor = (1.0, 0.0);
+    let mut simulation = Simulation::new(vec![dulum(0.3, 0.0, 1.0, 1.0), right]);
+    simulation
+        .springs
+        .push(Spring::new(Endpoint::Bob(0), Endpoint::Bob(1), 5.0, 1.0));
+    simulation.integrator = IntegratorKind::RungeKutta4.build();
+
+    let start = simulation.total_energy();
+    let mut largest_swing: f64 = 0.0;
+    simulation
+        .run_for(5.0, &mut |simulation| {
+            largest_swing = largest_swing.max(simulation.dulums[1].angle.abs());
+        })
+        .unwrap();
+
+    assert_close(simulation.total_energy(), start, 1e-6);
+    //the spring passed some of the swing over
+    assert!(
+        largest_swing > 0.05,
+        "second pendulum only swung {largest_swing}"
+    );
+}
+
+#[test]
+fn damped_spring_dissipation_adds_up() {
+    let mut simulation = Simulation::new(vec![dulum(1.0, 0.0, 1.0, 1.0)]);
+    simulation.springs.push(Spring {
+        damping: 2.0,
+        ..Spring::new(Endpoint::Bob(0), Endpoint::Fixed(-1.0, 1.0), 30.0, 1.0)
+    });
+    simulation.integrator = IntegratorKind::RungeKutta4.build();
+
+    let start = simulation.total_energy();
+    simulation.run_for(3.0, &mut |_| {}).unwrap();
+
+    assert!(simulation.dissipated > 0.1);
+    assert_close(
+        simulation.total_energy() + simulation.dissipated,
+        start,
+        1e-4,
+    );
+}
@@ -0,0 +1,52 @@
+use std::sync::atomic::AtomicUsize;
+
+use fyr_pendulum_too::{
+    phase::{Coordinate, Quantity},
+    pivot::{Pivot, PivotMotion},
+    simulation::Simulation,
+    sweep::{Parameter, Sampling, Sweep, SweepError},
+};
+
+use common::dulum;
+
+mod common;
+
+fn sweep(simulation: Simulation, parameter: Parameter) -> Sweep {
+    Sweep {
+        base: simulation,
+        parameter,
+        from: 0.0,
+        to: 0.1,
+        count: 3,
+        transient: 0.1,
+        duration: 0.35,
+        coordinate: Coordinate::new(0, Quantity::Angle),
+        sampling: Sampling::Stroboscopic { period: 0.1 },
+    }
+}
+
+#[test]
+fn amplitude_of_fixed_pivot_is_refused() {
+    let simulation = Simulation::new(vec![dulum(0.3, 0.0, 1.0, 1.0)]);
+
+    let fixed = sweep(simulation.clone(), Parameter::DrivingAmplitude);
+    assert_eq!(
+        fixed.check(),
+        Err(SweepError::NothingToSet(Parameter::DrivingAmplitude))
+    );
+    assert!(fixed.run(&AtomicUsize::new(0)).is_err());
+    assert!(sweep(simulation.clone(), Parameter::Mass(1))
+        .check()
+        .is_err());
+
+    let mut shaken = simulation;
+    shaken.pivot = Pivot::new(PivotMotion::Vertical {
+        amplitude: 0.1,
+        frequency: 2.0,
+    });
+    let results = sweep(shaken, Parameter::DrivingAmplitude)
+        .run(&AtomicUsize::new(0))
+        .unwrap();
+    assert_eq!(results.len(), 3);
+    assert!(results.iter().all(|(_, samples)| samples.len() == 3));
+}
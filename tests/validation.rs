@@ -0,0 +1,88 @@
+use fyr_pendulum_too::{
+    collision::Obstacle,
+    constraint::{Constraint, Endpoint},
+    dulum::Dulum,
+    simulation::{check_dulums, Simulation, SimulationError},
+    spring::Spring,
+};
+
+use common::dulum;
+
+mod common;
+
+fn chain() -> Vec<Dulum> {
+    (0..3).map(|_| dulum(0.5, 0.0, 1.0, 1.0)).collect()
+}
+
+#[test]
+fn degenerate_dulums_are_caught() {
+    assert_eq!(check_dulums(&chain()), Ok(()));
+
+    let mut dulums = chain();
+    dulums[1].len = 0.0;
+    assert_eq!(check_dulums(&dulums), Err(SimulationError::ZeroLength(1)));
+
+    for mass in [0.0, -1.0] {
+        let mut dulums = chain();
+        dulums[2].mass = mass;
+        assert_eq!(
+            check_dulums(&dulums),
+            Err(SimulationError::NonPositiveMass(2))
+        );
+    }
+
+    let mut dulums = chain();
+    dulums[0].angle_der = f64::NAN;
+    assert_eq!(check_dulums(&dulums), Err(SimulationError::NotFinite));
+}
+
+#[test]
+fn step_fails_instead_of_panicking() {
+    let broken: [fn(&mut Dulum); 3] = [
+        |dulum| dulum.len = 0.0,
+        |dulum| dulum.mass = 0.0,
+        |dulum| dulum.angle = f64::NAN,
+    ];
+    for break_dulum in broken {
+        let mut simulation = Simulation::new(chain());
+        break_dulum(&mut simulation.dulums[1]);
+        assert!(simulation.step(0.01).is_err());
+        assert!(simulation.substep().is_err());
+    }
+}
+
+#[test]
+fn non_positive_time_step_is_refused() {
+    for time_step in [0.0, -0.001, f32::NAN, f32::INFINITY] {
+        let mut simulation = Simulation::new(chain());
+        simulation.time_step = time_step;
+        assert!(matches!(
+            simulation.step(0.01),
+            Err(SimulationError::InvalidTimeStep(_))
+        ));
+        assert!(simulation.run_for(0.1, &mut |_| {}).is_err());
+    }
+}
+
+#[test]
+fn replaced_chain_drops_what_pointed_at_the_old_one() {
+    let mut simulation = Simulation::new(chain());
+    simulation.constraints.push(Constraint::Pin {
+        bob: 2,
+        position: (1.0, 1.0),
+    });
+    simulation
+        .springs
+        .push(Spring::new(Endpoint::Bob(0), Endpoint::Bob(2), 10.0, 1.0));
+    simulation
+        .collisions
+        .obstacles
+        .push(Obstacle::Floor { y: 2.0 });
+    simulation.run_for(0.1, &mut |_| {}).unwrap();
+
+    simulation.replace_dulums(chain());
+    assert!(simulation.constraints.is_empty());
+    assert!(simulation.springs.is_empty());
+    assert!(!simulation.collisions.is_active());
+    assert_eq!(simulation.time, 0.0);
+}